@@ -0,0 +1,41 @@
+#ifndef PIPE_LOGGER_H
+#define PIPE_LOGGER_H
+
+/*
+ * The C API of pipe-logger-lib, built with `cargo rustc --release --features capi --crate-type cdylib`.
+ * Strings need to be NUL-terminated UTF-8. A panic in Rust is returned as a failure instead of unwinding into C.
+ */
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct PipeLogger PipeLogger;
+
+/*
+ * Build a logger. `rotate_file_size` is the rotation size in bytes (0 to disable the rotation), `count` is the
+ * maximum number of log files (0 for no limit), and `compress` is whether to compress the rotated log files
+ * through xz (non-zero to enable). Returns NULL on failure. The logger needs to be released by `pipe_logger_close`.
+ */
+PipeLogger *pipe_logger_new(const char *log_path, uint64_t rotate_file_size, size_t count, int32_t compress);
+
+/* Write a string with a new line. Returns 1 if the log is rotated, 0 if not, or -1 on failure. */
+int32_t pipe_logger_write_line(PipeLogger *logger, const char *line);
+
+/* Rotate the log file now. Returns 1 if the log is rotated, 0 if the log file is empty, or -1 on failure. */
+int32_t pipe_logger_rotate(PipeLogger *logger);
+
+/*
+ * Sync the log file, wait for the pending compressions for at most `deadline_millis` milliseconds, and release
+ * the logger. Returns 0 if everything was finished, or -1 if not. The logger must not be used after this call.
+ */
+int32_t pipe_logger_close(PipeLogger *logger, uint64_t deadline_millis);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PIPE_LOGGER_H */
@@ -0,0 +1,88 @@
+use std::fmt::{self, Display, Formatter, Write};
+
+use chrono::{DateTime, FixedOffset};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An HTTP access record, formatted in the Apache combined log format.
+pub struct AccessRecord {
+    /// The IP address or the host name of the client (`%h`).
+    pub remote_host: String,
+    /// The RFC 1413 identity of the client (`%l`).
+    pub ident:       Option<String>,
+    /// The user ID of the authenticated user (`%u`).
+    pub user:        Option<String>,
+    /// The time that the request was received (`%t`).
+    pub time:        DateTime<FixedOffset>,
+    /// The request line from the client, such as `GET / HTTP/1.1` (`%r`).
+    pub request:     String,
+    /// The status code sent back to the client (`%>s`).
+    pub status:      u16,
+    /// The size of the response body in bytes (`%b`).
+    pub bytes:       Option<u64>,
+    /// The `Referer` request header.
+    pub referer:     Option<String>,
+    /// The `User-Agent` request header.
+    pub user_agent:  Option<String>,
+}
+
+impl Display for AccessRecord {
+    /// Format this record in the Apache combined log format, without a new line.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write_field(f, Some(&self.remote_host))?;
+        f.write_char(' ')?;
+        write_field(f, self.ident.as_ref())?;
+        f.write_char(' ')?;
+        write_field(f, self.user.as_ref())?;
+
+        f.write_fmt(format_args!(" [{}] ", self.time.format("%d/%b/%Y:%H:%M:%S %z")))?;
+
+        write_quoted_field(f, Some(&self.request))?;
+
+        f.write_fmt(format_args!(" {} ", self.status))?;
+
+        match self.bytes {
+            Some(bytes) => f.write_fmt(format_args!("{}", bytes))?,
+            None => f.write_char('-')?,
+        }
+
+        f.write_char(' ')?;
+        write_quoted_field(f, self.referer.as_ref())?;
+        f.write_char(' ')?;
+        write_quoted_field(f, self.user_agent.as_ref())
+    }
+}
+
+/// Write an unquoted field. An empty or absent value is written as `-`.
+fn write_field(f: &mut Formatter, value: Option<&String>) -> fmt::Result {
+    match value {
+        Some(value) if !value.is_empty() => write_escaped(f, value, true),
+        _ => f.write_char('-'),
+    }
+}
+
+/// Write a quoted field. An absent value is written as `"-"`.
+fn write_quoted_field(f: &mut Formatter, value: Option<&String>) -> fmt::Result {
+    f.write_char('"')?;
+
+    match value {
+        Some(value) => write_escaped(f, value, false)?,
+        None => f.write_char('-')?,
+    }
+
+    f.write_char('"')
+}
+
+/// Escape `"`, `\` and control characters (and spaces in unquoted fields) like Apache does, so that a record is always one parsable line.
+fn write_escaped(f: &mut Formatter, value: &str, escape_space: bool) -> fmt::Result {
+    for c in value.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            ' ' if escape_space => f.write_str("\\x20")?,
+            c if c.is_ascii_control() => f.write_fmt(format_args!("\\x{:02x}", c as u8))?,
+            c => f.write_char(c)?,
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Condvar, Mutex, PoisonError,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+pub(crate) type Job = Box<dyn FnOnce() + Send>;
+
+/// A thread which runs the jobs of a logger (processing the rotated log files and removing the ones out of the retention) one by one, in the order of submission. The thread is spawned on the first job, and it is joined when dropped, unless it is detached.
+pub(crate) struct BackgroundWorker {
+    sender:    Option<Sender<Job>>,
+    handle:    Option<JoinHandle<()>>,
+    spawned:   bool,
+    submitted: u64,
+    finished:  Arc<(Mutex<u64>, Condvar)>,
+}
+
+impl BackgroundWorker {
+    #[inline]
+    pub(crate) fn new() -> BackgroundWorker {
+        BackgroundWorker {
+            sender:    None,
+            handle:    None,
+            spawned:   false,
+            submitted: 0,
+            finished:  Arc::new((Mutex::new(0u64), Condvar::new())),
+        }
+    }
+
+    /// Spawn the thread. If it cannot be spawned, the jobs are run on the current thread.
+    fn spawn(&mut self) {
+        self.spawned = true;
+
+        let (sender, receiver) = mpsc::channel::<Job>();
+
+        let handle = {
+            let finished = self.finished.clone();
+
+            thread::Builder::new().name("pipe-logger".to_string()).spawn(move || {
+                for job in receiver {
+                    // a panicking processor must not stop the jobs after it
+                    if panic::catch_unwind(AssertUnwindSafe(job)).is_err() {
+                        // do nothing
+                    }
+
+                    let (count, condvar) = &*finished;
+
+                    *count.lock().unwrap_or_else(PoisonError::into_inner) += 1;
+
+                    condvar.notify_all();
+                }
+            })
+        };
+
+        if let Ok(handle) = handle {
+            self.sender = Some(sender);
+            self.handle = Some(handle);
+        }
+    }
+
+    /// Submit a job. Returns its ID, which is `0` if the job has been run on the current thread because the worker is gone or cannot be spawned.
+    pub(crate) fn submit(&mut self, job: Job) -> u64 {
+        if !self.spawned {
+            self.spawn();
+        }
+
+        let sender = match self.sender.as_ref() {
+            Some(sender) => sender,
+            None => {
+                job();
+
+                return 0;
+            },
+        };
+
+        match sender.send(job) {
+            Ok(_) => {
+                self.submitted += 1;
+
+                self.submitted
+            },
+            Err(mpsc::SendError(job)) => {
+                job();
+
+                0
+            },
+        }
+    }
+
+    #[inline]
+    fn finished(&self) -> u64 {
+        *self.finished.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Whether the job of an ID has been finished.
+    #[inline]
+    pub(crate) fn is_finished(&self, id: u64) -> bool {
+        self.finished() >= id
+    }
+
+    /// Whether all of the submitted jobs have been finished.
+    #[inline]
+    pub(crate) fn is_idle(&self) -> bool {
+        self.is_finished(self.submitted)
+    }
+
+    /// Wait until all of the submitted jobs are finished, or the timeout is reached. Returns whether the worker is idle.
+    pub(crate) fn wait_idle(&self, timeout: Option<Duration>) -> bool {
+        let start = Instant::now();
+
+        let (count, condvar) = &*self.finished;
+
+        let mut finished = count.lock().unwrap_or_else(PoisonError::into_inner);
+
+        while *finished < self.submitted {
+            finished = match timeout {
+                Some(timeout) => {
+                    let elapsed = start.elapsed();
+
+                    if elapsed >= timeout {
+                        return false;
+                    }
+
+                    condvar
+                        .wait_timeout(finished, timeout - elapsed)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                },
+                None => condvar.wait(finished).unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+
+        true
+    }
+
+    /// Let the thread finish the submitted jobs on its own, without being joined when dropped.
+    #[inline]
+    pub(crate) fn detach(&mut self) {
+        self.handle = None;
+    }
+}
+
+impl Drop for BackgroundWorker {
+    #[inline]
+    fn drop(&mut self) {
+        // the thread stops after the submitted jobs once the channel is closed
+        drop(self.sender.take());
+
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                // do nothing
+            }
+        }
+    }
+}
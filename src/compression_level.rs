@@ -0,0 +1,83 @@
+use std::fs::File;
+
+use crate::CompressionMethod;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The level used to compress rotated log files, in the range of the `CompressionMethod` (`0` to `9` for xz and gzip, and `1` to `22` for Zstandard).
+pub enum CompressionLevel {
+    /// Always use the same level.
+    Fixed(u32),
+    /// Choose the level by the free space of the file system every time a rotated log file is compressed.
+    Adaptive(AdaptiveCompressionLevel),
+}
+
+impl Default for CompressionLevel {
+    #[inline]
+    fn default() -> Self {
+        CompressionLevel::Fixed(9)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Use a fast level when there is plenty of free space, and the maximum level when the free space is tight. The free space is tight if it is less than `min_free_space`, or less than `min_free_file_sizes` times the size of the rotated log file. It is always considered tight if the free space cannot be known, such as on systems other than Unix-like ones.
+pub struct AdaptiveCompressionLevel {
+    /// The level used when there is plenty of free space.
+    pub fast_level:          u32,
+    /// The level used when the free space is tight.
+    pub max_level:           u32,
+    /// The minimum free space in bytes.
+    pub min_free_space:      u64,
+    /// The minimum free space in multiples of the size of the rotated log file.
+    pub min_free_file_sizes: u64,
+}
+
+impl CompressionLevel {
+    /// Find a level which is out of the range of `method`.
+    pub(crate) fn find_out_of_range(&self, method: CompressionMethod) -> Option<u32> {
+        let levels = match self {
+            CompressionLevel::Fixed(level) => [*level, *level],
+            CompressionLevel::Adaptive(adaptive) => [adaptive.fast_level, adaptive.max_level],
+        };
+
+        levels.into_iter().find(|level| !method.level_range().contains(level))
+    }
+
+    /// Get the level for compressing a file of `file_size` bytes into `file`.
+    pub(crate) fn resolve(&self, file: &File, file_size: u64) -> u32 {
+        match self {
+            CompressionLevel::Fixed(level) => *level,
+            CompressionLevel::Adaptive(adaptive) => {
+                let min_free_space = adaptive
+                    .min_free_space
+                    .max(file_size.saturating_mul(adaptive.min_free_file_sizes));
+
+                match free_space(file) {
+                    Some(free_space) if free_space >= min_free_space => adaptive.fast_level,
+                    _ => adaptive.max_level,
+                }
+            },
+        }
+    }
+}
+
+/// The free space in bytes, available to unprivileged users, of the file system where a file is.
+#[cfg(unix)]
+pub(crate) fn free_space(file: &File) -> Option<u64> {
+    use std::{mem::MaybeUninit, os::unix::io::AsRawFd};
+
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+
+    if unsafe { libc::fstatvfs(file.as_raw_fd(), stat.as_mut_ptr()) } != 0 {
+        return None;
+    }
+
+    let stat = unsafe { stat.assume_init() };
+
+    #[allow(clippy::unnecessary_cast)]
+    Some((stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn free_space(_file: &File) -> Option<u64> {
+    None
+}
@@ -0,0 +1,171 @@
+use std::{
+    io::{self, Read, Write},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "gzip")]
+use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+use xz2::{read::XzDecoder, write::XzEncoder};
+
+use crate::create_compressor;
+
+/// The extensions of compressed rotated log files in all of the methods, whether their features are enabled or not, so that the files compressed by another build are still found and cleaned up by the retention.
+pub(crate) const COMPRESSED_EXTENSIONS: [&str; 3] = [".xz", ".gz", ".zst"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The format to compress rotated log files. The compression level (`PipeLoggerBuilder::set_compression_level`) is used by all of the formats in their own ranges (see `level_range`), and only xz can use multiple threads. The variants depend on the enabled features, so it is non-exhaustive.
+#[non_exhaustive]
+pub enum CompressionMethod {
+    /// xz, with the `.xz` extension.
+    Xz,
+    /// gzip, with the `.gz` extension. It needs the `gzip` feature.
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Zstandard, with the `.zst` extension. It needs the `zstd` feature.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Default for CompressionMethod {
+    #[inline]
+    fn default() -> Self {
+        CompressionMethod::Xz
+    }
+}
+
+impl CompressionMethod {
+    /// The extension appended to the names of the compressed files, including the dot.
+    #[inline]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CompressionMethod::Xz => ".xz",
+            #[cfg(feature = "gzip")]
+            CompressionMethod::Gzip => ".gz",
+            #[cfg(feature = "zstd")]
+            CompressionMethod::Zstd => ".zst",
+        }
+    }
+
+    /// Get the method of an extension, including the dot. Returns `None` if it is unknown or its feature is disabled.
+    #[inline]
+    pub fn from_extension(extension: &str) -> Option<CompressionMethod> {
+        match extension {
+            ".xz" => Some(CompressionMethod::Xz),
+            #[cfg(feature = "gzip")]
+            ".gz" => Some(CompressionMethod::Gzip),
+            #[cfg(feature = "zstd")]
+            ".zst" => Some(CompressionMethod::Zstd),
+            _ => None,
+        }
+    }
+
+    /// The range of the compression levels: `0` to `9` for xz and gzip, and `1` to `22` for Zstandard.
+    #[inline]
+    pub fn level_range(&self) -> RangeInclusive<u32> {
+        match self {
+            CompressionMethod::Xz => 0..=9,
+            #[cfg(feature = "gzip")]
+            CompressionMethod::Gzip => 0..=9,
+            #[cfg(feature = "zstd")]
+            CompressionMethod::Zstd => 1..=22,
+        }
+    }
+
+    pub(crate) fn encoder<W: Write>(
+        &self,
+        writer: W,
+        compression_threads: usize,
+        compression_level: u32,
+    ) -> io::Result<Encoder<W>> {
+        Ok(match self {
+            CompressionMethod::Xz => {
+                Encoder::Xz(create_compressor(writer, compression_threads, compression_level))
+            },
+            #[cfg(feature = "gzip")]
+            CompressionMethod::Gzip => {
+                Encoder::Gzip(GzEncoder::new(writer, Compression::new(compression_level)))
+            },
+            #[cfg(feature = "zstd")]
+            CompressionMethod::Zstd => {
+                Encoder::Zstd(zstd::Encoder::new(writer, compression_level as i32)?)
+            },
+        })
+    }
+
+    /// Create a decoder which also reads the concatenated streams, such as the ones merged by the compaction.
+    pub(crate) fn decoder<'a, R: Read + 'a>(&self, reader: R) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            CompressionMethod::Xz => Box::new(XzDecoder::new_multi_decoder(reader)),
+            #[cfg(feature = "gzip")]
+            CompressionMethod::Gzip => Box::new(MultiGzDecoder::new(reader)),
+            #[cfg(feature = "zstd")]
+            CompressionMethod::Zstd => Box::new(zstd::Decoder::new(reader)?),
+        })
+    }
+}
+
+/// The encoder of a `CompressionMethod`.
+pub(crate) enum Encoder<W: Write> {
+    Xz(XzEncoder<W>),
+    #[cfg(feature = "gzip")]
+    Gzip(GzEncoder<W>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> Encoder<W> {
+    /// Finish the stream and return the writer.
+    pub(crate) fn finish(self) -> io::Result<W> {
+        match self {
+            Encoder::Xz(encoder) => encoder.finish(),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => encoder.finish(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Encoder::Xz(encoder) => encoder.write(buf),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Encoder::Xz(encoder) => encoder.flush(),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Strip the extension of a compressed file from a file name. Returns the stripped name and the extension.
+#[inline]
+pub(crate) fn strip_compressed_extension(file_name: &str) -> Option<(&str, &'static str)> {
+    COMPRESSED_EXTENSIONS
+        .iter()
+        .find_map(|extension| Some((file_name.strip_suffix(extension)?, *extension)))
+}
+
+/// The paths of a rotated log file compressed in all of the methods.
+#[inline]
+pub(crate) fn compressed_paths<'a>(
+    folder_path: &'a Path,
+    rotated_log_file_name: &'a str,
+) -> impl Iterator<Item = PathBuf> + 'a {
+    COMPRESSED_EXTENSIONS.iter().map(move |extension| {
+        Path::join(folder_path, format!("{}{}", rotated_log_file_name, extension))
+    })
+}
@@ -0,0 +1,47 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug)]
+/// Raised when a rotated log file has failed a processor after all of the retries and has been parked in the dead-letter directory.
+pub struct DeadLetter {
+    /// The path of the parked file in the dead-letter directory.
+    pub path:      PathBuf,
+    /// The `Debug` representation of the failed processor.
+    pub processor: String,
+    /// The error returned by the last attempt.
+    pub error:     io::Error,
+}
+
+pub(crate) type DeadLetterCallback = Box<dyn Fn(DeadLetter) + Send + Sync>;
+
+/// Move a file into the dead-letter directory, and write the error into a `.error` sidecar file next to it. Returns the path of the parked file.
+pub(crate) fn park(
+    dead_letter_dir: &Path,
+    file: &Path,
+    processor: &str,
+    error: &io::Error,
+) -> io::Result<PathBuf> {
+    fs::create_dir_all(dead_letter_dir)?;
+
+    let file_name = match file.file_name() {
+        Some(file_name) => file_name,
+        None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "The file has no name.")),
+    };
+
+    let parked = Path::join(dead_letter_dir, file_name);
+
+    // the dead-letter directory may be on another file system
+    if fs::rename(file, &parked).is_err() {
+        fs::copy(file, &parked)?;
+        fs::remove_file(file)?;
+    }
+
+    let mut sidecar = parked.clone().into_os_string();
+    sidecar.push(".error");
+
+    fs::write(sidecar, format!("processor: {}\nerror: {}\n", processor, error))?;
+
+    Ok(parked)
+}
@@ -0,0 +1,396 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    iter,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+
+use crate::{
+    compression_level::free_space,
+    compression_method::{compressed_paths, strip_compressed_extension},
+    scan_rotated_log_file_names, split_log_path, PipeLoggerBuilder, RotationNaming,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// How serious a `Finding` is.
+pub enum Severity {
+    /// Just for the record.
+    Info,
+    /// Something may go wrong later.
+    Warning,
+    /// The logger cannot work properly.
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The health checks done by `PipeLogger::doctor`.
+pub enum DoctorCheck {
+    /// Whether the log file can be opened for appending, or created.
+    Writability,
+    /// Whether the file system has enough free space for the rotations.
+    FreeSpace,
+    /// Whether the rotated log files can be read, and have the configured file mode.
+    Permissions,
+    /// Whether there are compressions or compactions left unfinished by a crash.
+    PartialCompression,
+    /// Whether there are files which look like rotated log files but are not named in the rotation naming. The rotated log files of the child loggers are not counted, but a child logger whose name begins with a digit cannot be told from a misnamed rotated log file.
+    Naming,
+    /// Whether the clock is behind the timestamps of the rotated log files.
+    Clock,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A finding of `PipeLogger::doctor`.
+pub struct Finding {
+    pub severity: Severity,
+    pub check:    DoctorCheck,
+    /// The file which the finding is about, if any.
+    pub path:     Option<PathBuf>,
+    pub message:  String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The findings of `PipeLogger::doctor`, in the order of the checks.
+pub struct DoctorReport {
+    pub findings: Vec<Finding>,
+}
+
+impl DoctorReport {
+    /// Whether there is no finding of `Severity::Error`.
+    #[inline]
+    pub fn is_healthy(&self) -> bool {
+        self.findings.iter().all(|finding| finding.severity < Severity::Error)
+    }
+
+    /// The findings of `Severity::Warning` or `Severity::Error`.
+    #[inline]
+    pub fn problems(&self) -> impl Iterator<Item = &Finding> {
+        self.findings.iter().filter(|finding| finding.severity > Severity::Info)
+    }
+}
+
+/// Free space under this multiple of the rotation size is a warning.
+const MIN_FREE_ROTATION_SIZES: u64 = 2;
+
+pub(crate) fn diagnose<P: AsRef<Path>>(builder: &PipeLoggerBuilder<P>) -> DoctorReport {
+    let mut findings = Vec::new();
+
+    let mut add = |severity, check, path: Option<&Path>, message: String| {
+        findings.push(Finding {
+            severity,
+            check,
+            path: path.map(Path::to_path_buf),
+            message,
+        })
+    };
+
+    let (file_path, folder_path, file_name, file_name_point_index) =
+        match split_log_path(builder.log_path().as_ref(), builder.name_split()) {
+            Ok(split) => split,
+            Err(err) => {
+                add(
+                    Severity::Error,
+                    DoctorCheck::Writability,
+                    Some(builder.log_path().as_ref()),
+                    format!("The log path is invalid: {}", err),
+                );
+
+                return DoctorReport {
+                    findings,
+                };
+            },
+        };
+
+    // writability
+    if !folder_path.is_dir() {
+        add(
+            Severity::Error,
+            DoctorCheck::Writability,
+            Some(&folder_path),
+            "The directory of the log file does not exist.".to_string(),
+        );
+
+        return DoctorReport {
+            findings,
+        };
+    }
+
+    if file_path.exists() {
+        match OpenOptions::new().append(true).open(&file_path) {
+            Ok(_) => add(
+                Severity::Info,
+                DoctorCheck::Writability,
+                Some(&file_path),
+                "The log file is writable.".to_string(),
+            ),
+            Err(err) => add(
+                Severity::Error,
+                DoctorCheck::Writability,
+                Some(&file_path),
+                format!("The log file cannot be opened for appending: {}", err),
+            ),
+        }
+    } else {
+        // the log file is not created by a diagnosis, so a probe file is created instead
+        let probe = Path::join(&folder_path, format!(".{}.doctor", file_name));
+
+        match File::create(&probe) {
+            Ok(_) => {
+                if fs::remove_file(&probe).is_err() {
+                    // do nothing
+                }
+
+                add(
+                    Severity::Info,
+                    DoctorCheck::Writability,
+                    Some(&file_path),
+                    "The log file does not exist, and can be created.".to_string(),
+                );
+            },
+            Err(err) => add(
+                Severity::Error,
+                DoctorCheck::Writability,
+                Some(&folder_path),
+                format!("The log file cannot be created in the directory: {}", err),
+            ),
+        }
+    }
+
+    // free space
+    match File::open(&folder_path).ok().and_then(|folder| free_space(&folder)) {
+        Some(free_space) => match builder.rotate().and_then(|rotate| rotate.file_size()) {
+            Some(size) if free_space < size.saturating_mul(MIN_FREE_ROTATION_SIZES) => add(
+                Severity::Warning,
+                DoctorCheck::FreeSpace,
+                Some(&folder_path),
+                format!(
+                    "Only {} bytes are free, which is less than {} times the rotation size.",
+                    free_space, MIN_FREE_ROTATION_SIZES
+                ),
+            ),
+            _ => add(
+                Severity::Info,
+                DoctorCheck::FreeSpace,
+                Some(&folder_path),
+                format!("{} bytes are free.", free_space),
+            ),
+        },
+        None => add(
+            Severity::Info,
+            DoctorCheck::FreeSpace,
+            Some(&folder_path),
+            "The free space cannot be known.".to_string(),
+        ),
+    }
+
+    let mut rotated_log_file_names = match scan_rotated_log_file_names(
+        &folder_path,
+        &file_name,
+        file_name_point_index,
+        builder.rotation_naming(),
+    ) {
+        Ok(rotated_log_file_names) => rotated_log_file_names,
+        Err(err) => {
+            add(
+                Severity::Error,
+                DoctorCheck::Permissions,
+                Some(&folder_path),
+                format!("The directory cannot be read: {}", err),
+            );
+
+            return DoctorReport {
+                findings,
+            };
+        },
+    };
+
+    // a file being compressed is found in both of the forms
+    rotated_log_file_names.dedup();
+
+    // permissions and partial compressions
+    for rotated_log_file_name in rotated_log_file_names.iter() {
+        let rotated_log_file = Path::join(&folder_path, rotated_log_file_name);
+
+        let rotated_log_files_compressed: Vec<PathBuf> =
+            compressed_paths(&folder_path, rotated_log_file_name).collect();
+
+        for rotated_log_file_compressed in rotated_log_files_compressed.iter() {
+            if rotated_log_file.is_file() && rotated_log_file_compressed.is_file() {
+                add(
+                    Severity::Warning,
+                    DoctorCheck::PartialCompression,
+                    Some(rotated_log_file_compressed),
+                    "The compression was not finished. It is restarted when the logger is built."
+                        .to_string(),
+                );
+            }
+        }
+
+        for path in [&rotated_log_file].into_iter().chain(rotated_log_files_compressed.iter()) {
+            if !path.is_file() {
+                continue;
+            }
+
+            if let Err(err) = File::open(path) {
+                add(
+                    Severity::Warning,
+                    DoctorCheck::Permissions,
+                    Some(path),
+                    format!("The rotated log file cannot be read: {}", err),
+                );
+            }
+
+            #[cfg(unix)]
+            if let Some(file_mode) = builder.file_mode() {
+                use std::os::unix::fs::PermissionsExt;
+
+                if let Ok(metadata) = fs::metadata(path) {
+                    let mode = metadata.permissions().mode() & 0o7777;
+
+                    if mode != file_mode & 0o7777 {
+                        add(
+                            Severity::Warning,
+                            DoctorCheck::Permissions,
+                            Some(path),
+                            format!("The file mode is {:o} rather than {:o}.", mode, file_mode),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    // naming and leftovers of compactions
+    let (rotated_stem, rotated_ext) =
+        builder.rotation_naming().split(&file_name, file_name_point_index);
+
+    // the pattern has been compiled by the scan
+    let re = Regex::new(&builder.rotation_naming().pattern().unwrap()).unwrap();
+
+    let is_rotated = |name: &str| {
+        varying_part(name, rotated_stem, rotated_ext).map_or(false, |varying| re.is_match(varying))
+    };
+
+    if let Ok(entries) = folder_path.read_dir() {
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect();
+
+        names.sort();
+
+        for name in names {
+            let path = Path::join(&folder_path, &name);
+
+            if let Some(compacting) =
+                name.strip_prefix('.').and_then(|name| name.strip_suffix(".compacting"))
+            {
+                let plain_name =
+                    strip_compressed_extension(compacting).map_or(compacting, |(name, _)| name);
+
+                if is_rotated(plain_name) {
+                    add(
+                        Severity::Warning,
+                        DoctorCheck::PartialCompression,
+                        Some(&path),
+                        "The compaction was not finished. The file can be removed.".to_string(),
+                    );
+                }
+
+                continue;
+            }
+
+            let plain_name =
+                strip_compressed_extension(&name).map_or(name.as_str(), |(name, _)| name);
+
+            let resembles = varying_part(plain_name, rotated_stem, rotated_ext)
+                .map_or(false, |varying| builder.rotation_naming().resembles(varying));
+
+            if resembles
+                && !rotated_log_file_names.iter().any(|n| n == plain_name)
+                && !is_rotated_by_child(
+                    plain_name,
+                    &file_name,
+                    file_name_point_index,
+                    builder.rotation_naming(),
+                    &re,
+                )
+            {
+                add(
+                    Severity::Warning,
+                    DoctorCheck::Naming,
+                    Some(&path),
+                    "The file looks like a rotated log file, but is not named in the rotation \
+                     naming, so it is neither counted nor removed by the retention."
+                        .to_string(),
+                );
+            }
+        }
+    }
+
+    // clock
+    let now: DateTime<Utc> = (builder.clock())();
+
+    let newest = rotated_log_file_names.iter().rev().find_map(|name| {
+        let varying = varying_part(name, rotated_stem, rotated_ext)?;
+
+        builder.rotation_naming().parse_timestamp(varying, builder.local_time())
+    });
+
+    match newest {
+        Some(newest) if newest > now => add(
+            Severity::Warning,
+            DoctorCheck::Clock,
+            None,
+            format!(
+                "The clock ({}) is behind the newest rotated log file ({}), so the rotated log \
+                 files may be ordered wrongly.",
+                now.to_rfc3339(),
+                newest.to_rfc3339()
+            ),
+        ),
+        _ => add(
+            Severity::Info,
+            DoctorCheck::Clock,
+            None,
+            format!("The clock is at {}.", now.to_rfc3339()),
+        ),
+    }
+
+    DoctorReport {
+        findings,
+    }
+}
+
+/// The part of a file name between the stem and the extension of the rotated log files.
+fn varying_part<'a>(name: &'a str, stem: &str, ext: &str) -> Option<&'a str> {
+    name.strip_prefix(stem)?.strip_suffix(ext)
+}
+
+/// Whether a file is a rotated log file of a child logger, whose log file is named `<stem>-<name><extension>`.
+fn is_rotated_by_child(
+    name: &str,
+    file_name: &str,
+    file_name_point_index: usize,
+    rotation_naming: &RotationNaming,
+    re: &Regex,
+) -> bool {
+    let child_prefix = format!("{}-", &file_name[..file_name_point_index]);
+    let ext = &file_name[file_name_point_index..];
+
+    let rest = match name.strip_prefix(child_prefix.as_str()) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    // try every possible name of the child logger
+    rest.char_indices().skip(1).map(|(index, _)| index).chain(iter::once(rest.len())).any(|index| {
+        let child_file_name = format!("{}{}{}", child_prefix, &rest[..index], ext);
+
+        let (stem, ext) = rotation_naming.split(&child_file_name, child_prefix.len() + index);
+
+        varying_part(name, stem, ext).map_or(false, |varying| re.is_match(varying))
+    })
+}
@@ -0,0 +1,209 @@
+use std::{collections::HashMap, io, path::PathBuf};
+
+use regex::Regex;
+
+use crate::PipeLogger;
+
+const DEFAULT_MAX_OPEN_LOGGERS: usize = 64;
+
+type KeyExtractor = Box<dyn Fn(&str) -> Option<String> + Send>;
+
+/// Route each record to a lazily-created child logger (see `PipeLogger::child`) of a base logger by a routing key extracted from the record. Records without a routing key are written by the base logger. All of the child loggers share the rotation, compression and retention settings of the base logger.
+pub struct DynamicRouter {
+    base:             PipeLogger,
+    key_extractor:    KeyExtractor,
+    max_open_loggers: usize,
+    rotate_together:  bool,
+    /// Ordered from the least recently used to the most recently used.
+    loggers:          Vec<(String, PipeLogger)>,
+    /// The names of the rotated log files of the closed child loggers, so that reopening them does not scan the directory and recover it again.
+    closed_loggers:   HashMap<String, Vec<String>>,
+}
+
+impl DynamicRouter {
+    /// Create a new DynamicRouter. The routing key returned from `key_extractor` is used as the name of a child logger, where characters other than ASCII alphanumerics, `-` and `_` are replaced with `_`.
+    pub fn new<F: Fn(&str) -> Option<String> + Send + 'static>(
+        base: PipeLogger,
+        key_extractor: F,
+    ) -> DynamicRouter {
+        DynamicRouter {
+            base,
+            key_extractor: Box::new(key_extractor),
+            max_open_loggers: DEFAULT_MAX_OPEN_LOGGERS,
+            rotate_together: false,
+            loggers: Vec::new(),
+            closed_loggers: HashMap::new(),
+        }
+    }
+
+    /// Create a new DynamicRouter whose routing key is the first capture group of the regular expression `pattern` (e.g. `^\[([a-z]+)\]` for `[service] ...`). Records which do not match are written by the base logger.
+    pub fn with_regex(base: PipeLogger, pattern: &str) -> io::Result<DynamicRouter> {
+        let regex =
+            Regex::new(pattern).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+        Ok(DynamicRouter::new(base, move |text| {
+            regex
+                .captures(text)
+                .and_then(|captures| captures.get(1))
+                .map(|m| m.as_str().to_string())
+        }))
+    }
+
+    /// The maximum number of child loggers (log files) being open at the same time. The least recently used one is closed when the limit is exceeded.
+    pub fn max_open_loggers(&self) -> usize {
+        self.max_open_loggers
+    }
+
+    /// The maximum number of child loggers (log files) being open at the same time. The least recently used one is closed when the limit is exceeded. The minimum value is `1`.
+    pub fn set_max_open_loggers(&mut self, max_open_loggers: usize) -> &mut Self {
+        self.max_open_loggers = max_open_loggers.max(1);
+
+        while self.loggers.len() > self.max_open_loggers {
+            self.close_least_recently_used();
+        }
+
+        self
+    }
+
+    /// Whether to rotate all of the open loggers together when one of them is rotated.
+    pub fn rotate_together(&self) -> bool {
+        self.rotate_together
+    }
+
+    /// Whether to rotate all of the open loggers together when one of them is rotated, so that their rotated log files cover the same periods. Empty log files are not rotated, and loggers closed by the `max_open_loggers` limit are not rotated either. If some of the other loggers fail to be rotated, the rest are still rotated, and the write returns the error after the record has been written.
+    pub fn set_rotate_together(&mut self, rotate_together: bool) -> &mut Self {
+        self.rotate_together = rotate_together;
+        self
+    }
+
+    /// The base logger.
+    pub fn base(&mut self) -> &mut PipeLogger {
+        &mut self.base
+    }
+
+    /// Write a string to the logger selected by its routing key. If the log is rotated, this method returns the renamed path.
+    pub fn write<S: AsRef<str>>(&mut self, text: S) -> io::Result<Option<PathBuf>> {
+        let text = text.as_ref();
+
+        let index = self.route(text)?;
+
+        let new_file = self.logger(index).write(text)?;
+
+        if new_file.is_some() {
+            self.rotate_others(index)?;
+        }
+
+        Ok(new_file)
+    }
+
+    /// Write a string with a new line to the logger selected by its routing key. If the log is rotated, this method returns the renamed path.
+    pub fn write_line<S: AsRef<str>>(&mut self, text: S) -> io::Result<Option<PathBuf>> {
+        let text = text.as_ref();
+
+        let index = self.route(text)?;
+
+        let new_file = self.logger(index).write_line(text)?;
+
+        if new_file.is_some() {
+            self.rotate_others(index)?;
+        }
+
+        Ok(new_file)
+    }
+
+    /// Close the least recently used child logger without waiting for its background worker, which finishes the pending jobs on its own.
+    fn close_least_recently_used(&mut self) {
+        let (key, mut logger) = self.loggers.remove(0);
+
+        logger.worker.detach();
+
+        self.closed_loggers.insert(key, std::mem::take(&mut logger.rotated_log_file_names));
+    }
+
+    /// Select the logger for a record. Returns the index of the child logger, or `None` for the base logger.
+    fn route(&mut self, text: &str) -> io::Result<Option<usize>> {
+        let key = match (self.key_extractor)(text) {
+            Some(key) => sanitize_key(&key),
+            None => return Ok(None),
+        };
+
+        match self.loggers.iter().position(|(k, _)| *k == key) {
+            Some(index) => {
+                let entry = self.loggers.remove(index);
+
+                self.loggers.push(entry);
+            },
+            None => {
+                let logger = self
+                    .base
+                    .child_builder(&key)
+                    .build_with_rotated_log_file_names(self.closed_loggers.remove(&key))
+                    .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+                if self.loggers.len() >= self.max_open_loggers {
+                    self.close_least_recently_used();
+                }
+
+                self.loggers.push((key, logger));
+            },
+        }
+
+        Ok(Some(self.loggers.len() - 1))
+    }
+
+    #[inline]
+    fn logger(&mut self, index: Option<usize>) -> &mut PipeLogger {
+        match index {
+            Some(index) => &mut self.loggers[index].1,
+            None => &mut self.base,
+        }
+    }
+
+    /// Rotate the open loggers other than the one at `index` if `rotate_together` is enabled. Every logger is tried even if some of them fail, and the failures are returned together.
+    fn rotate_others(&mut self, index: Option<usize>) -> io::Result<()> {
+        if !self.rotate_together {
+            return Ok(());
+        }
+
+        let mut errors = Vec::new();
+
+        if index.is_some() {
+            if let Err(err) = self.base.rotate() {
+                errors.push(("the base logger".to_string(), err));
+            }
+        }
+
+        for (i, (key, logger)) in self.loggers.iter_mut().enumerate() {
+            if Some(i) != index {
+                if let Err(err) = logger.rotate() {
+                    errors.push((format!("the logger of `{}`", key), err));
+                }
+            }
+        }
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0).1),
+            _ => {
+                let reasons: Vec<String> =
+                    errors.iter().map(|(logger, err)| format!("{}: {}", logger, err)).collect();
+
+                Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!(
+                        "{} loggers cannot be rotated together. {}",
+                        errors.len(),
+                        reasons.join("; ")
+                    ),
+                ))
+            },
+        }
+    }
+}
+
+#[inline]
+fn sanitize_key(key: &str) -> String {
+    key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
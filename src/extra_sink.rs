@@ -0,0 +1,47 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    io::{self, Write},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use crate::write_all_parts;
+
+pub(crate) type SinkErrorCallback = Box<dyn Fn(&io::Error) + Send>;
+
+/// A writer receiving a copy of the log output besides the tee output. It is shared by the builder, its clones and the loggers built from them.
+#[derive(Clone)]
+pub(crate) struct ExtraSink(Arc<Mutex<Box<dyn Write + Send>>>);
+
+impl ExtraSink {
+    #[inline]
+    pub(crate) fn new(sink: Box<dyn Write + Send>) -> ExtraSink {
+        ExtraSink(Arc::new(Mutex::new(sink)))
+    }
+
+    /// Write the parts of some data as a whole, so that the writes of the loggers sharing this sink are not interleaved.
+    #[inline]
+    pub(crate) fn write_all_parts(&self, parts: &[&[u8]]) -> io::Result<()> {
+        let mut sink = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+
+        write_all_parts(&mut *sink, parts)
+    }
+
+    #[inline]
+    pub(crate) fn flush(&self) -> io::Result<()> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).flush()
+    }
+}
+
+impl Debug for ExtraSink {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("ExtraSink")
+    }
+}
+
+impl PartialEq for ExtraSink {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
@@ -0,0 +1,16 @@
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// What was still in progress when `PipeLogger::flush_within` returned.
+pub struct FlushReport {
+    /// The compressed log files which were still being produced when the deadline was reached.
+    pub pending_compressions: Vec<PathBuf>,
+}
+
+impl FlushReport {
+    /// Whether everything was durable before the deadline.
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.pending_compressions.is_empty()
+    }
+}
@@ -0,0 +1,94 @@
+use std::{
+    path::Path,
+    ptr,
+    sync::{
+        atomic::{AtomicPtr, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use crate::{PipeLogger, PipeLoggerBuilder, PipeLoggerBuilderError};
+
+static GLOBAL_LOGGER: AtomicPtr<Mutex<PipeLogger>> = AtomicPtr::new(ptr::null_mut());
+
+/// Build a `PipeLogger` and install it as the process-global logger, which can be accessed by the `global` function. It can only be initialized once. If the heartbeat interval is set and the logger is not deterministic, a background thread is started to write the heartbeat lines.
+///
+/// With the `log` feature, the same logger is also installed as the logger of the `log` crate at `LevelFilter::Info` in the `DEFAULT_RECORD_FORMAT`, after it is installed as the global logger. If another logger of the `log` crate has been installed, the global logger stays installed, and `PipeLoggerBuilderError::LogLoggerAlreadyInitialized` is returned. `PipeLoggerBackend::init` installs the global logger as well.
+pub fn init<P: AsRef<Path>>(builder: PipeLoggerBuilder<P>) -> Result<(), PipeLoggerBuilderError> {
+    if is_initialized() {
+        return Err(PipeLoggerBuilderError::GlobalLoggerAlreadyInitialized);
+    }
+
+    let logger = Arc::new(Mutex::new(builder.build()?));
+
+    install(logger.clone())?;
+
+    #[cfg(feature = "log")]
+    crate::PipeLoggerBackend::from_shared(logger, log::LevelFilter::Info).register()?;
+
+    Ok(())
+}
+
+#[inline]
+pub(crate) fn is_initialized() -> bool {
+    !GLOBAL_LOGGER.load(Ordering::Acquire).is_null()
+}
+
+/// Install a logger as the process-global logger, and start the heartbeat thread if needed.
+pub(crate) fn install(logger: Arc<Mutex<PipeLogger>>) -> Result<(), PipeLoggerBuilderError> {
+    let logger = Arc::into_raw(logger) as *mut Mutex<PipeLogger>;
+
+    match GLOBAL_LOGGER.compare_exchange(
+        ptr::null_mut(),
+        logger,
+        Ordering::AcqRel,
+        Ordering::Acquire,
+    ) {
+        Ok(_) => {
+            let logger = unsafe { &*logger };
+
+            let heartbeat_interval = {
+                let logger = logger.lock().unwrap();
+
+                logger.heartbeat_interval().filter(|_| !logger.deterministic)
+            };
+
+            if let Some(interval) = heartbeat_interval {
+                thread::spawn(move || loop {
+                    thread::sleep(interval);
+
+                    match logger.lock() {
+                        Ok(mut logger) => {
+                            if let Err(err) = logger.write_heartbeat() {
+                                eprintln!("{}", err);
+                            }
+                        },
+                        // the logger is broken by a panic
+                        Err(_) => break,
+                    }
+                });
+            }
+
+            Ok(())
+        },
+        Err(_) => {
+            // another thread has initialized the global logger in the meantime
+            drop(unsafe { Arc::from_raw(logger) });
+
+            Err(PipeLoggerBuilderError::GlobalLoggerAlreadyInitialized)
+        },
+    }
+}
+
+/// Get the process-global logger installed by the `init` function (or `PipeLoggerBackend::init`).
+pub fn global() -> Option<&'static Mutex<PipeLogger>> {
+    let logger = GLOBAL_LOGGER.load(Ordering::Acquire);
+
+    if logger.is_null() {
+        None
+    } else {
+        // the global logger is never dropped once it is installed
+        Some(unsafe { &*logger })
+    }
+}
@@ -70,23 +70,96 @@ Rotate again!
 ```
 */
 
+mod access_record;
+mod background_worker;
+mod compression_level;
+mod compression_method;
+mod dead_letter;
+mod doctor;
+mod dynamic_router;
+mod extra_sink;
+mod flush_report;
+mod global;
+#[cfg(feature = "log")]
+mod log_backend;
+mod log_rates;
+mod migrate_naming;
+mod name_split;
+mod pipe_log_searcher;
+#[cfg(feature = "capi")]
+pub mod pipe_logger_capi;
+#[cfg(feature = "python")]
+pub mod python;
+mod reopen_check;
 mod rotate_method;
+mod rotation_naming;
+mod rotation_processor;
+#[cfg(all(feature = "systemd", unix))]
+mod sd_notify;
+mod security_event;
+mod shutdown_report;
+mod tee_buffer;
+mod thinning;
+mod timestamp_precision;
+mod volume_alert;
+mod w3c;
 
 use std::{
+    collections::{BTreeMap, HashSet},
     error::Error,
-    fmt::{Display, Error as FmtError, Formatter},
+    fmt::{self, Display, Error as FmtError, Formatter},
     fs::{self, File, OpenOptions},
-    io::{self, Read, Write},
+    io::{self, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
+    sync::Arc,
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use chrono::{DateTime, Utc};
+pub use access_record::AccessRecord;
+use background_worker::BackgroundWorker;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+pub use compression_level::{AdaptiveCompressionLevel, CompressionLevel};
+pub use compression_method::CompressionMethod;
+use compression_method::{compressed_paths, strip_compressed_extension, COMPRESSED_EXTENSIONS};
+pub use dead_letter::DeadLetter;
+use dead_letter::DeadLetterCallback;
+pub use doctor::{DoctorCheck, DoctorReport, Finding, Severity};
+pub use dynamic_router::DynamicRouter;
+use extra_sink::{ExtraSink, SinkErrorCallback};
+pub use flush_report::FlushReport;
+pub use global::{global, init};
+#[cfg(feature = "log")]
+pub use log_backend::{PipeLoggerBackend, DEFAULT_RECORD_FORMAT};
+use log_rates::RateCounter;
+pub use log_rates::{LogRates, Rate};
+pub use migrate_naming::{migrate_naming, migrate_naming_with_name_split};
+use name_split::rotated_base_name;
+pub use name_split::NameSplit;
 use path_absolutize::*;
+pub use pipe_log_searcher::{Match, PipeLogSearcher};
 use regex::Regex;
+use reopen_check::{check_reopened_log_file, is_log_file_moved, ReopenMismatchCallback};
+pub use reopen_check::{ReopenMismatch, ReopenMismatchReason};
 pub use rotate_method::RotateMethod;
-use xz2::write::XzEncoder;
+pub use rotation_naming::RotationNaming;
+#[allow(deprecated)]
+pub use rotation_processor::XzCompression;
+pub use rotation_processor::{Compression, Mirror, RotationMarker, RotationProcessor};
+#[cfg(all(feature = "systemd", unix))]
+use sd_notify::sd_notify;
+pub use security_event::{SecurityEvent, SecurityEventFormat, SecurityEventHeader};
+pub use shutdown_report::ShutdownReport;
+use tee_buffer::TeeBuffer;
+pub use thinning::Thinning;
+pub use timestamp_precision::TimestampPrecision;
+use volume_alert::VolumeMonitor;
+pub use volume_alert::{VolumeAlert, VolumeThreshold};
+use w3c::{format_w3c_entry, write_w3c_header};
+use xz2::{
+    stream::{Check, MtStreamBuilder},
+    write::XzEncoder,
+};
 
 const BUFFER_SIZE: usize = 4096 * 4;
 const FILE_WAIT_MILLI_SECONDS: u64 = 30;
@@ -99,10 +172,22 @@ pub enum PipeLoggerBuilderError {
     RotateFileSizeTooSmall,
     /// A valid count of log files needs bigger than 0.
     CountTooSmall,
+    /// A valid rotation interval needs longer than 0.
+    RotateIntervalTooShort,
     /// std::io::Error.
     IOError(io::Error),
     /// A log file cannot be a directory. Wrap the absolutized log file.
     FileIsDirectory(PathBuf),
+    /// The global logger can only be initialized once.
+    GlobalLoggerAlreadyInitialized,
+    /// The logger of the `log` crate has been installed by another one.
+    LogLoggerAlreadyInitialized,
+    /// The log file is locked by another logger. Wrap the absolutized log file.
+    AlreadyLocked(PathBuf),
+    /// The template of `RotationNaming::Template` is invalid, or the rotation naming cannot be used with the other settings. Wrap the reason.
+    InvalidRotationNaming(String),
+    /// The compression level is out of the range of the compression method. Wrap the level.
+    InvalidCompressionLevel(u32),
 }
 
 impl Display for PipeLoggerBuilderError {
@@ -115,11 +200,29 @@ impl Display for PipeLoggerBuilderError {
             PipeLoggerBuilderError::CountTooSmall => {
                 f.write_str("A valid count of log files needs bigger than 0.")
             },
+            PipeLoggerBuilderError::RotateIntervalTooShort => {
+                f.write_str("A valid rotation interval needs longer than 0.")
+            },
             PipeLoggerBuilderError::IOError(err) => Display::fmt(err, f),
             PipeLoggerBuilderError::FileIsDirectory(path) => f.write_fmt(format_args!(
                 "A log file cannot be a directory. The path of that file is `{}`.",
                 path.to_string_lossy()
             )),
+            PipeLoggerBuilderError::GlobalLoggerAlreadyInitialized => {
+                f.write_str("The global logger can only be initialized once.")
+            },
+            PipeLoggerBuilderError::LogLoggerAlreadyInitialized => {
+                f.write_str("The logger of the `log` crate has been installed by another one.")
+            },
+            PipeLoggerBuilderError::AlreadyLocked(path) => f.write_fmt(format_args!(
+                "The log file is locked by another logger. The path of that file is `{}`.",
+                path.to_string_lossy()
+            )),
+            PipeLoggerBuilderError::InvalidRotationNaming(reason) => f.write_str(reason),
+            PipeLoggerBuilderError::InvalidCompressionLevel(level) => f.write_fmt(format_args!(
+                "The compression level {} is out of the range of the compression method.",
+                level
+            )),
         }
     }
 }
@@ -140,7 +243,7 @@ impl From<PathBuf> for PipeLoggerBuilderError {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 /// Read from standard input and write to standard output.
 pub enum Tee {
     /// To stdout.
@@ -149,14 +252,52 @@ pub enum Tee {
     Stderr,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 /// To build a PipeLogger instance.
 pub struct PipeLoggerBuilder<P: AsRef<Path>> {
-    rotate:   Option<RotateMethod>,
-    count:    Option<usize>,
-    log_path: P,
-    compress: bool,
-    tee:      Option<Tee>,
+    rotate:              Option<RotateMethod>,
+    count:               Option<usize>,
+    thinning:            Option<Thinning>,
+    max_age:             Option<Duration>,
+    max_total_size:      Option<u64>,
+    compaction_size:     Option<u64>,
+    background_removal:  bool,
+    min_file_age:        Duration,
+    max_backlog_growth:  u64,
+    rotate_on_build:     bool,
+    retention_exemption: Option<Regex>,
+    log_path:            P,
+    compress:            bool,
+    compression_threads: usize,
+    compression_tail:    Option<u64>,
+    compression_level:   CompressionLevel,
+    compression_verify:  bool,
+    compress_method:     CompressionMethod,
+    processors:          Vec<Arc<dyn RotationProcessor>>,
+    processor_retries:   u32,
+    retry_delay:         Duration,
+    dead_letter_dir:     Option<PathBuf>,
+    external_rotation:   bool,
+    reopen_on_move:      bool,
+    lock:                bool,
+    file_mode:           Option<u32>,
+    follow_symlinks:     bool,
+    verify_reopen:       bool,
+    w3c_fields:          Option<Vec<String>>,
+    tee:                 Option<Tee>,
+    tee_buffer:          Option<usize>,
+    extra_sink:          Option<ExtraSink>,
+    heartbeat_interval:  Option<Duration>,
+    final_marker:        Option<String>,
+    rotate_on_close:     bool,
+    rotation_sequence:   bool,
+    name_split:          NameSplit,
+    rotation_naming:     RotationNaming,
+    timestamp_precision: TimestampPrecision,
+    local_time:          bool,
+    deterministic:       bool,
+    clock:               fn() -> DateTime<Utc>,
+    min_valid_time:      Option<DateTime<Utc>>,
 }
 
 impl<P: AsRef<Path>> PipeLoggerBuilder<P> {
@@ -165,541 +306,3152 @@ impl<P: AsRef<Path>> PipeLoggerBuilder<P> {
         PipeLoggerBuilder {
             rotate: None,
             count: None,
+            thinning: None,
+            max_age: None,
+            max_total_size: None,
+            compaction_size: None,
+            background_removal: false,
+            min_file_age: Duration::ZERO,
+            max_backlog_growth: 1,
+            rotate_on_build: false,
+            retention_exemption: None,
             log_path,
             compress: false,
+            compression_threads: 1,
+            compression_tail: None,
+            compression_level: CompressionLevel::default(),
+            compression_verify: false,
+            compress_method: CompressionMethod::default(),
+            processors: Vec::new(),
+            processor_retries: 0,
+            retry_delay: Duration::from_secs(1),
+            dead_letter_dir: None,
+            external_rotation: false,
+            reopen_on_move: false,
+            lock: false,
+            file_mode: None,
+            follow_symlinks: true,
+            verify_reopen: false,
+            w3c_fields: None,
             tee: None,
+            tee_buffer: None,
+            extra_sink: None,
+            heartbeat_interval: None,
+            final_marker: None,
+            rotate_on_close: false,
+            rotation_sequence: false,
+            name_split: NameSplit::default(),
+            rotation_naming: RotationNaming::default(),
+            timestamp_precision: TimestampPrecision::default(),
+            local_time: false,
+            deterministic: false,
+            clock: Utc::now,
+            min_valid_time: None,
         }
     }
 
-    pub fn rotate(&self) -> &Option<RotateMethod> {
-        &self.rotate
+    pub fn rotate(&self) -> Option<RotateMethod> {
+        self.rotate
     }
 
-    pub fn count(&self) -> &Option<usize> {
-        &self.count
+    pub fn count(&self) -> Option<usize> {
+        self.count
     }
 
-    pub fn log_path(&self) -> &P {
-        &self.log_path
+    /// The thinning retention of the rotated log files.
+    pub fn thinning(&self) -> Option<Thinning> {
+        self.thinning
     }
 
-    /// Whether to compress the rotated log files through xz.
-    pub fn compress(&self) -> bool {
-        self.compress
+    /// The age after which the rotated log files are removed.
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age
     }
 
-    pub fn tee(&self) -> &Option<Tee> {
-        &self.tee
+    /// The budget in bytes of the total size of the rotated log files.
+    pub fn max_total_size(&self) -> Option<u64> {
+        self.max_total_size
     }
 
-    pub fn set_rotate(&mut self, rotate: Option<RotateMethod>) -> &mut Self {
-        self.rotate = rotate;
-        self
+    /// The size under which consecutive rotated log files are merged.
+    pub fn compaction_size(&self) -> Option<u64> {
+        self.compaction_size
     }
 
-    pub fn set_count(&mut self, count: Option<usize>) -> &mut Self {
-        self.count = count;
-        self
+    /// Whether the rotated log files are removed by the retention in a background thread.
+    pub fn background_retention(&self) -> bool {
+        self.background_removal
     }
 
-    /// Whether to compress the rotated log files through xz.
-    pub fn set_compress(&mut self, compress: bool) -> &mut Self {
-        self.compress = compress;
-        self
+    /// The minimum age of the log file before it can be rotated.
+    pub fn min_file_age_before_rotation(&self) -> Duration {
+        self.min_file_age
     }
 
-    pub fn set_tee(&mut self, tee: Option<Tee>) -> &mut Self {
-        self.tee = tee;
-        self
+    /// The maximum multiple of the rotation size when the processing of the rotated log files falls behind.
+    pub fn max_backlog_growth(&self) -> u64 {
+        self.max_backlog_growth
     }
 
-    /// Build a new PipeLogger.
-    pub fn build(self) -> Result<PipeLogger, PipeLoggerBuilderError> {
-        if let Some(rotate) = &self.rotate {
-            match rotate {
-                RotateMethod::FileSize(file_size) => {
-                    if *file_size < 2 {
-                        return Err(PipeLoggerBuilderError::RotateFileSizeTooSmall);
-                    }
-                },
-            }
+    /// Whether to rotate an oversized existing log file when the logger is built.
+    pub fn rotate_on_build(&self) -> bool {
+        self.rotate_on_build
+    }
 
-            if let Some(count) = &self.count {
-                if *count < 1 {
-                    return Err(PipeLoggerBuilderError::CountTooSmall);
-                }
-            }
-        }
+    /// The pattern of the names of the rotated log files which are exempt from the `count` retention.
+    pub fn retention_exemption(&self) -> &Option<Regex> {
+        &self.retention_exemption
+    }
 
-        let file_path = self.log_path.as_ref().absolutize()?;
+    pub fn log_path(&self) -> &P {
+        &self.log_path
+    }
 
-        let file_size;
+    /// Whether to compress the rotated log files in the `compress_method`.
+    pub fn compress(&self) -> bool {
+        self.compress
+    }
 
-        let folder_path = match file_path.metadata() {
-            Ok(metadata) => {
-                if metadata.is_dir() {
-                    return Err(PipeLoggerBuilderError::FileIsDirectory(file_path.into_owned()));
-                }
+    /// The number of threads used to compress a rotated log file. `0` means using all of the available CPU cores.
+    pub fn compression_threads(&self) -> usize {
+        self.compression_threads
+    }
 
-                let p = metadata.permissions();
+    /// The maximum size in bytes of a rotated log file to be kept when it is compressed. Only the tail is stored if the file is bigger than it.
+    pub fn compression_tail(&self) -> Option<u64> {
+        self.compression_tail
+    }
 
-                if p.readonly() {
-                    return Err(PipeLoggerBuilderError::IOError(io::Error::new(
-                        io::ErrorKind::PermissionDenied,
-                        format!("`{}` is readonly.", file_path.to_str().unwrap()),
-                    )));
-                }
+    /// The level used to compress the rotated log files.
+    pub fn compression_level(&self) -> CompressionLevel {
+        self.compression_level
+    }
 
-                file_size = metadata.len();
-
-                match file_path.parent() {
-                    Some(parent) => {
-                        if self.rotate.is_some() {
-                            match fs::metadata(parent) {
-                                Ok(m) => {
-                                    let p = m.permissions();
-                                    if p.readonly() {
-                                        return Err(PipeLoggerBuilderError::IOError(
-                                            io::Error::new(
-                                                io::ErrorKind::PermissionDenied,
-                                                format!(
-                                                    "`{}` is readonly.",
-                                                    parent.to_str().unwrap()
-                                                ),
-                                            ),
-                                        ));
-                                    }
-                                },
-                                Err(err) => {
-                                    return Err(PipeLoggerBuilderError::IOError(err));
-                                },
-                            }
-                        }
-                        parent
-                    },
-                    None => unreachable!(),
-                }
-            },
-            Err(_) => {
-                file_size = 0;
-
-                match file_path.parent() {
-                    Some(parent) => match fs::metadata(parent) {
-                        Ok(m) => {
-                            let p = m.permissions();
-                            if p.readonly() {
-                                return Err(PipeLoggerBuilderError::IOError(io::Error::new(
-                                    io::ErrorKind::PermissionDenied,
-                                    format!("`{}` is readonly.", parent.to_str().unwrap()),
-                                )));
-                            }
-                            parent
-                        },
-                        Err(err) => {
-                            return Err(PipeLoggerBuilderError::IOError(err));
-                        },
-                    },
-                    None => {
-                        return Err(PipeLoggerBuilderError::IOError(io::Error::new(
-                            io::ErrorKind::NotFound,
-                            format!("`{}`'s parent does not exist.", file_path.to_str().unwrap()),
-                        )));
-                    },
-                }
-            },
-        }
-        .to_path_buf();
+    /// Whether to verify a compressed log file before removing the uncompressed one.
+    pub fn compression_verify(&self) -> bool {
+        self.compression_verify
+    }
 
-        let file_name =
-            Path::new(file_path.as_ref()).file_name().unwrap().to_str().unwrap().to_string();
+    /// The format to compress the rotated log files.
+    pub fn compress_method(&self) -> CompressionMethod {
+        self.compress_method
+    }
 
-        let file_name_point_index = match file_name.rfind('.') {
-            Some(index) => index,
-            None => file_name.len(),
-        };
+    /// The processors executed in order on every rotated log file, after the compression if it is enabled.
+    pub fn processors(&self) -> &[Arc<dyn RotationProcessor>] {
+        &self.processors
+    }
 
-        let rotated_log_file_names = {
-            let mut rotated_log_file_names = Vec::new();
+    /// The number of times a failed processor is retried on a rotated log file.
+    pub fn processor_retries(&self) -> u32 {
+        self.processor_retries
+    }
 
-            let re = Regex::new("^-[1-2][0-9]{3}(-[0-5][0-9]){5}-[0-9]{3}$").unwrap(); // -%Y-%m-%d-%H-%M-%S + $.3f
+    /// The delay before the first retry of a failed processor.
+    pub fn retry_delay(&self) -> Duration {
+        self.retry_delay
+    }
 
-            let file_name_without_extension = &file_name[..file_name_point_index];
+    /// The directory where the rotated log files which keep failing a processor are parked.
+    pub fn dead_letter_dir(&self) -> &Option<PathBuf> {
+        &self.dead_letter_dir
+    }
 
-            for entry in folder_path.read_dir().unwrap().filter_map(|entry| entry.ok()) {
-                let rotated_log_file_path = entry.path();
+    /// Whether the log file is rotated by an external tool such as logrotate with `copytruncate`.
+    pub fn external_rotation(&self) -> bool {
+        self.external_rotation
+    }
 
-                if !rotated_log_file_path.is_file() {
-                    continue;
-                }
+    /// Whether the log file is reopened when it is moved or deleted by somebody else.
+    pub fn reopen_on_move(&self) -> bool {
+        self.reopen_on_move
+    }
 
-                let rotated_log_file_name =
-                    Path::new(&rotated_log_file_path).file_name().unwrap().to_str().unwrap();
+    /// Whether to take an exclusive lock on the log file.
+    pub fn lock(&self) -> bool {
+        self.lock
+    }
 
-                if !rotated_log_file_name.starts_with(file_name_without_extension) {
-                    continue;
-                }
+    /// The permission mode of the log file.
+    pub fn file_mode(&self) -> Option<u32> {
+        self.file_mode
+    }
 
-                let rotated_log_file_name_point_index = match rotated_log_file_name.rfind('.') {
-                    Some(index) => index,
-                    None => rotated_log_file_name.len(),
-                };
+    /// Whether the log file can be a symbolic link.
+    pub fn follow_symlinks(&self) -> bool {
+        self.follow_symlinks
+    }
 
-                if rotated_log_file_name_point_index < file_name_point_index + 24 {
-                    // -%Y-%m-%d-%H-%M-%S + $.3f
-                    continue;
-                }
+    /// Whether to verify the log file reopened after a rotation.
+    pub fn verify_reopen(&self) -> bool {
+        self.verify_reopen
+    }
 
-                let file_name_without_extension_len = file_name_without_extension.len();
+    /// The field identifiers of the W3C extended log format.
+    pub fn w3c_fields(&self) -> &Option<Vec<String>> {
+        &self.w3c_fields
+    }
 
-                if !re.is_match(
-                    &rotated_log_file_name
-                        [file_name_without_extension_len..file_name_without_extension_len + 24],
-                ) {
-                    // -%Y-%m-%d-%H-%M-%S + $.3f
-                    continue;
-                }
+    pub fn tee(&self) -> Option<Tee> {
+        self.tee.clone()
+    }
 
-                let ext = &rotated_log_file_name[rotated_log_file_name_point_index..];
+    /// The maximum number of pending writes to the tee output buffered in a background thread.
+    pub fn tee_buffer(&self) -> Option<usize> {
+        self.tee_buffer
+    }
 
-                if ext.eq(&file_name[file_name_point_index..]) {
-                    rotated_log_file_names.push(rotated_log_file_name.to_string());
-                } else if ext.eq(".xz")
-                    && rotated_log_file_name[..rotated_log_file_name_point_index]
-                        .ends_with(&file_name[file_name_point_index..])
-                {
-                    rotated_log_file_names.push(
-                        rotated_log_file_name[..rotated_log_file_name_point_index].to_string(),
-                    );
-                }
-            }
+    /// Whether a writer receives a copy of the log output.
+    pub fn has_extra_sink(&self) -> bool {
+        self.extra_sink.is_some()
+    }
 
-            rotated_log_file_names.sort_unstable();
+    /// The interval of the heartbeat lines written by the global logger.
+    pub fn heartbeat_interval(&self) -> Option<Duration> {
+        self.heartbeat_interval
+    }
 
-            rotated_log_file_names
-        };
+    /// The line written to the log file when the logger is closed.
+    pub fn final_marker(&self) -> Option<&str> {
+        self.final_marker.as_deref()
+    }
 
-        let file =
-            OpenOptions::new().create(true).write(true).append(true).open(file_path.as_ref())?;
+    /// Whether to rotate the log file when the logger is closed.
+    pub fn rotate_on_close(&self) -> bool {
+        self.rotate_on_close
+    }
 
-        Ok(PipeLogger {
-            rotate: self.rotate,
-            count: self.count,
-            file: Some(file),
-            file_name,
-            file_name_point_index,
-            file_path: file_path.into_owned(),
-            file_size,
-            folder_path,
-            rotated_log_file_names,
-            compress: self.compress,
-            tee: self.tee,
-            last_rotated_time: 0,
-        })
+    /// Whether to append a sequence number to the names of the rotated log files.
+    pub fn rotation_sequence(&self) -> bool {
+        self.rotation_sequence
     }
-}
 
-// TODO -----PipeLoggerBuilder END-----
+    /// How to split the file name of the log file into the stem and the extension.
+    pub fn name_split(&self) -> &NameSplit {
+        &self.name_split
+    }
 
-// TODO -----PipeLogger START-----
+    /// How to name the rotated log files.
+    pub fn rotation_naming(&self) -> &RotationNaming {
+        &self.rotation_naming
+    }
 
-/// PipeLogger can help you stores, rotates and compresses logs.
-pub struct PipeLogger {
-    rotate:                 Option<RotateMethod>,
-    count:                  Option<usize>,
-    file:                   Option<File>,
-    file_name:              String,
-    file_name_point_index:  usize,
-    file_path:              PathBuf,
-    file_size:              u64,
-    folder_path:            PathBuf,
-    rotated_log_file_names: Vec<String>,
-    compress:               bool,
-    tee:                    Option<Tee>,
-    last_rotated_time:      i64,
-}
+    /// The precision of the timestamps in the names of the rotated log files.
+    pub fn timestamp_precision(&self) -> TimestampPrecision {
+        self.timestamp_precision
+    }
 
-impl Write for PipeLogger {
-    /// Write UTF-8 data.
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        PipeLogger::write(self, String::from_utf8_lossy(buf))?;
+    /// Whether the timestamps in the names of the rotated log files are in local time rather than UTC.
+    pub fn local_time(&self) -> bool {
+        self.local_time
+    }
 
-        Ok(buf.len())
+    /// Whether to do everything on the thread which writes to the logger.
+    pub fn deterministic(&self) -> bool {
+        self.deterministic
     }
 
-    fn flush(&mut self) -> io::Result<()> {
-        match self.file {
-            Some(ref mut file) => file.flush(),
-            None => unreachable!(),
-        }
+    /// The function to get the current time.
+    pub fn clock(&self) -> fn() -> DateTime<Utc> {
+        self.clock
     }
-}
 
-impl PipeLogger {
-    /// Create a new PipeLoggerBuilder.
-    pub fn builder<P: AsRef<Path>>(log_path: P) -> PipeLoggerBuilder<P> {
-        PipeLoggerBuilder::new(log_path)
+    /// The earliest time which the clock is trusted to be synced at.
+    pub fn min_valid_time(&self) -> Option<DateTime<Utc>> {
+        self.min_valid_time
     }
 
-    /// Write a string. If the log is rotated, this method returns the renamed path.
-    pub fn write<S: AsRef<str>>(&mut self, text: S) -> io::Result<Option<PathBuf>> {
-        let s = text.as_ref();
+    pub fn set_rotate(&mut self, rotate: Option<RotateMethod>) -> &mut Self {
+        self.rotate = rotate;
+        self
+    }
 
-        let buf = s.as_bytes();
+    pub fn set_count(&mut self, count: Option<usize>) -> &mut Self {
+        self.count = count;
+        self
+    }
 
-        let len = buf.len();
+    /// Thin out the older rotated log files every time the log file is rotated. It can be used along with the `count` retention, which is applied to the files remaining after the thinning. The files exempt from the `count` retention are exempt from the thinning as well. It cannot be used with `RotationNaming::Sequential`.
+    pub fn set_thinning(&mut self, thinning: Option<Thinning>) -> &mut Self {
+        self.thinning = thinning;
+        self
+    }
 
-        if len == 0 {
-            return Ok(None);
-        }
+    /// Remove the rotated log files older than this age (by the timestamps in their names) every time the log file is rotated. The files exempt from the `count` retention are kept. It cannot be used with `RotationNaming::Sequential`.
+    pub fn set_max_age(&mut self, max_age: Option<Duration>) -> &mut Self {
+        self.max_age = max_age;
+        self
+    }
 
-        self.print(s);
+    /// Remove the oldest rotated log files every time the log file is rotated, until the total size of the rotated log files (on the disk, so compressed files are measured by their compressed sizes) fits in this budget in bytes. The newest rotated log file and the files exempt from the `count` retention are kept, but the exempt files are still counted in the total size. It cannot be used with `RotationNaming::Sequential`.
+    pub fn set_max_total_size(&mut self, max_total_size: Option<u64>) -> &mut Self {
+        self.max_total_size = max_total_size;
+        self
+    }
 
-        let mut file = self.file.take().unwrap();
+    /// Merge the runs of consecutive rotated log files smaller than this size (on the disk, so compressed files are measured by their compressed sizes) into the newest file of each run, every time the log file is rotated. The files are concatenated in order, and compressed files are concatenated as multi-stream files, which are never merged with the ones compressed in another format. The files still being processed and the files exempt from the `count` retention are not merged. It cannot be used with `RotationNaming::Sequential`.
+    pub fn set_compaction_size(&mut self, compaction_size: Option<u64>) -> &mut Self {
+        self.compaction_size = compaction_size;
+        self
+    }
 
-        let n = file.write(buf)?;
+    /// Remove the rotated log files selected by the retention in a background thread, so that a slow file system (such as a network one) does not stall the writes. The removals of a rotation wait for the ones of the previous rotation to finish, and closing the logger waits for them until the deadline. It is ignored in the deterministic mode.
+    pub fn set_background_retention(&mut self, background_retention: bool) -> &mut Self {
+        self.background_removal = background_retention;
+        self
+    }
 
-        self.file_size += n as u64;
+    /// The minimum age of the log file before it can be rotated, so that a flood of output right after the logger is built or the log file is rotated does not cause a storm of rotations. The age is counted from when this logger opened the log file, and the log file can grow beyond the rotation size meanwhile.
+    pub fn set_min_file_age_before_rotation(&mut self, min_file_age: Duration) -> &mut Self {
+        self.min_file_age = min_file_age;
+        self
+    }
 
-        let mut new_file = None;
+    /// The maximum multiple of the rotation size when the processing (such as the compression) of the rotated log files falls behind. If it is bigger than `1`, the log file is rotated at `(1 + n)` times the rotation size while `n` rotated log files are still being processed, so that fewer and larger rotations are done until the processing catches up. It is `1` (disabled) by default.
+    pub fn set_max_backlog_growth(&mut self, max_backlog_growth: u64) -> &mut Self {
+        self.max_backlog_growth = max_backlog_growth;
+        self
+    }
 
-        if let Some(rotate) = &self.rotate {
-            match rotate {
-                RotateMethod::FileSize(size) => {
-                    if self.file_size >= *size {
-                        let utc: DateTime<Utc> = {
-                            let mut utc: DateTime<Utc> = Utc::now();
-                            let mut millisecond = utc.timestamp_millis();
-                            while self.last_rotated_time == millisecond {
-                                // Especially for Windows, because its time precision is about 15ms.
-                                thread::sleep(Duration::from_millis(FILE_WAIT_MILLI_SECONDS));
-                                utc = Utc::now();
-                                millisecond = utc.timestamp_millis();
-                            }
-                            self.last_rotated_time = millisecond;
-                            utc
-                        };
+    /// Rotate the existing log file when the logger is built if it has already reached the rotation size, such as after a crash in the middle of a storm, rather than waiting for the first write. The rotated log file is named after the time when the log file was last modified.
+    pub fn set_rotate_on_build(&mut self, rotate_on_build: bool) -> &mut Self {
+        self.rotate_on_build = rotate_on_build;
+        self
+    }
 
-                        let timestamp = utc.format("%Y-%m-%d-%H-%M-%S").to_string();
-                        let millisecond = utc.format("%.3f").to_string();
+    /// The pattern of the names of the rotated log files (without the extension of the compression) which are exempt from the `count` retention. Exempt files are neither removed nor counted.
+    pub fn set_retention_exemption(&mut self, retention_exemption: Option<Regex>) -> &mut Self {
+        self.retention_exemption = retention_exemption;
+        self
+    }
 
-                        file.flush()?;
+    /// Whether to compress the rotated log files in the `compress_method`.
+    pub fn set_compress(&mut self, compress: bool) -> &mut Self {
+        self.compress = compress;
+        self
+    }
 
-                        file.sync_all()?;
+    /// The number of threads used to compress a rotated log file. `0` means using all of the available CPU cores.
+    pub fn set_compression_threads(&mut self, compression_threads: usize) -> &mut Self {
+        self.compression_threads = compression_threads;
+        self
+    }
 
-                        drop(file);
+    /// The maximum size in bytes of a rotated log file to be kept when it is compressed. Only the tail is stored if the file is bigger than it.
+    pub fn set_compression_tail(&mut self, compression_tail: Option<u64>) -> &mut Self {
+        self.compression_tail = compression_tail;
+        self
+    }
 
-                        let rotated_log_file_name = format!(
-                            "{}-{}-{}{}",
-                            &self.file_name[..self.file_name_point_index],
-                            timestamp,
-                            &millisecond[1..],
-                            &self.file_name[self.file_name_point_index..]
-                        );
+    /// The level used to compress the rotated log files, in the range of the `compress_method`. It is `CompressionLevel::Fixed(9)` by default. `build` fails if it is out of the range.
+    pub fn set_compression_level(&mut self, compression_level: CompressionLevel) -> &mut Self {
+        self.compression_level = compression_level;
+        self
+    }
 
-                        let rotated_log_file =
-                            Path::join(&self.folder_path, Path::new(&rotated_log_file_name));
+    /// Whether to verify a compressed log file before removing the uncompressed one. If it is `true`, the compressed log file is synced, decoded and compared with the uncompressed one, and it is removed instead if they are different, so that the compression fails and can be retried.
+    pub fn set_compression_verify(&mut self, compression_verify: bool) -> &mut Self {
+        self.compression_verify = compression_verify;
+        self
+    }
 
-                        fs::copy(&self.file_path, &rotated_log_file)?;
+    /// The format to compress the rotated log files. It is `CompressionMethod::Xz` by default. The rotated log files compressed in any format are recognized regardless of this setting.
+    pub fn set_compress_method(&mut self, compress_method: CompressionMethod) -> &mut Self {
+        self.compress_method = compress_method;
+        self
+    }
 
-                        if self.compress {
-                            let rotated_log_file_name_compressed =
-                                format!("{}.xz", rotated_log_file_name);
-                            let rotated_log_file_compressed = Path::join(
-                                &self.folder_path,
-                                Path::new(&rotated_log_file_name_compressed),
-                            );
-                            let rotated_log_file = rotated_log_file.clone();
+    /// The processors executed in order on every rotated log file in a background thread, after the compression if it is enabled. Each processor receives the path returned by the previous one, and the chain stops at the first error.
+    pub fn set_processors(&mut self, processors: Vec<Arc<dyn RotationProcessor>>) -> &mut Self {
+        self.processors = processors;
+        self
+    }
 
-                            let tee = self.tee.clone();
+    /// Append a processor to the end of the processor chain.
+    pub fn add_processor<R: RotationProcessor + 'static>(&mut self, processor: R) -> &mut Self {
+        self.processors.push(Arc::new(processor));
+        self
+    }
 
-                            let print_err = move |s| match tee {
-                                Some(tee) => match tee {
-                                    Tee::Stdout => {
-                                        eprintln!("{}", s);
-                                    },
-                                    Tee::Stderr => {
-                                        println!("{}", s);
-                                    },
-                                },
-                                None => {
-                                    eprintln!("{}", s);
-                                },
-                            };
-
-                            thread::spawn(move || {
-                                match File::create(&rotated_log_file_compressed) {
-                                    Ok(file_w) => {
-                                        match File::open(&rotated_log_file) {
-                                            Ok(mut file_r) => {
-                                                let mut compressor = XzEncoder::new(file_w, 9);
-                                                let mut buffer = [0u8; BUFFER_SIZE];
-                                                loop {
-                                                    match file_r.read(&mut buffer) {
-                                                        Ok(c) => {
-                                                            if c == 0 {
-                                                                drop(file_r);
-                                                                if fs::remove_file(
-                                                                    &rotated_log_file,
-                                                                )
-                                                                .is_err()
-                                                                {
-                                                                    // do nothing
-                                                                }
-                                                                break;
-                                                            }
-                                                            match compressor.write(&buffer[..c]) {
-                                                                Ok(cc) => {
-                                                                    if c != cc {
-                                                                        print_err(
-                                                                            "The space is not \
-                                                                             enough."
-                                                                                .to_string(),
-                                                                        );
-                                                                        break;
-                                                                    }
-                                                                },
-                                                                Err(err) => {
-                                                                    print_err(err.to_string());
-                                                                    break;
-                                                                },
-                                                            }
-                                                        },
-                                                        Err(ref err)
-                                                            if err.kind()
-                                                                == io::ErrorKind::NotFound =>
-                                                        {
-                                                            // The rotated log file is deleted because of the count limit
-                                                            drop(compressor);
-                                                            if fs::remove_file(
-                                                                &rotated_log_file_compressed,
-                                                            )
-                                                            .is_err()
-                                                            {
-                                                                // do nothing
-                                                            }
-                                                            break;
-                                                        },
-                                                        Err(err) => {
-                                                            print_err(err.to_string());
-                                                            break;
-                                                        },
-                                                    }
-                                                }
-                                            },
-                                            Err(ref err)
-                                                if err.kind() == io::ErrorKind::NotFound =>
-                                            {
-                                                // The rotated log file is deleted because of the count limit
-                                                drop(file_w);
-                                                if fs::remove_file(&rotated_log_file_compressed)
-                                                    .is_err()
-                                                {
-                                                }
-                                            },
-                                            Err(err) => {
-                                                print_err(err.to_string());
-                                            },
-                                        }
-                                    },
-                                    Err(err) => {
-                                        print_err(err.to_string());
-                                    },
-                                };
-                            });
-                        }
+    /// The number of times a failed processor (including the compression) is retried on a rotated log file before giving up.
+    pub fn set_processor_retries(&mut self, processor_retries: u32) -> &mut Self {
+        self.processor_retries = processor_retries;
+        self
+    }
 
-                        self.rotated_log_file_names.push(rotated_log_file_name);
-
-                        if let Some(count) = self.count {
-                            while self.rotated_log_file_names.len() >= count {
-                                let mut rotated_log_file_name =
-                                    self.rotated_log_file_names.remove(0);
-                                if fs::remove_file(Path::join(
-                                    &self.folder_path,
-                                    Path::new(&rotated_log_file_name),
-                                ))
-                                .is_err()
-                                {
-                                    // do nothing
-                                }
+    /// The delay before the first retry of a failed processor. It is doubled after every retry.
+    pub fn set_retry_delay(&mut self, retry_delay: Duration) -> &mut Self {
+        self.retry_delay = retry_delay;
+        self
+    }
 
-                                let p_compressed_name = {
-                                    rotated_log_file_name.push_str(".xz");
+    /// The directory where a file which fails a processor after all of the retries is moved to, along with a `.error` sidecar file describing the failure. If it is not set, the file is left where it is and the error is only printed.
+    pub fn set_dead_letter_dir(&mut self, dead_letter_dir: Option<PathBuf>) -> &mut Self {
+        self.dead_letter_dir = dead_letter_dir;
+        self
+    }
 
-                                    rotated_log_file_name
-                                };
+    /// Whether the log file is rotated by an external tool such as logrotate with `copytruncate`. If it is `true`, the truncation of the log file is detected on every write, and `rotate` is ignored.
+    pub fn set_external_rotation(&mut self, external_rotation: bool) -> &mut Self {
+        self.external_rotation = external_rotation;
+        self
+    }
 
-                                let p_compressed =
-                                    Path::join(&self.folder_path, Path::new(&p_compressed_name));
-                                if fs::remove_file(p_compressed).is_err() {}
-                            }
-                        }
+    /// Whether to reopen (or create) the log file when it is moved or deleted by somebody else, such as logrotate without `copytruncate`. If it is `true`, the path of the log file is checked on every write. A moved log file is detected by its device and inode numbers on Unix-like systems, and only a deleted one is detected on other systems. See also `PipeLogger::reopen`.
+    pub fn set_reopen_on_move(&mut self, reopen_on_move: bool) -> &mut Self {
+        self.reopen_on_move = reopen_on_move;
+        self
+    }
 
-                        file =
-                            OpenOptions::new().write(true).truncate(true).open(&self.file_path)?;
+    /// Whether to take an exclusive lock on the log file, so that building another logger for the same file fails with `PipeLoggerBuilderError::AlreadyLocked`. It is an advisory lock (`flock`) on Unix-like systems and a sharing restriction on Windows.
+    pub fn set_lock(&mut self, lock: bool) -> &mut Self {
+        self.lock = lock;
+        self
+    }
 
-                        self.file_size = 0;
+    /// The permission mode of the log file (e.g. `0o640`), set exactly regardless of the umask. It only takes effect on Unix-like systems. Rotated and compressed log files always have the same permissions (and owner, if possible) as the log file.
+    pub fn set_file_mode(&mut self, file_mode: Option<u32>) -> &mut Self {
+        self.file_mode = file_mode;
+        self
+    }
 
-                        new_file = if self.compress {
-                            let mut s = rotated_log_file.into_os_string();
-                            s.push(".xz");
-                            Some(PathBuf::from(s))
-                        } else {
-                            Some(rotated_log_file)
-                        };
-                    }
-                },
-            }
-        }
+    /// Whether the log file can be a symbolic link. If it is `false`, opening the log file through a symbolic link fails (`O_NOFOLLOW`) on Unix-like systems.
+    pub fn set_follow_symlinks(&mut self, follow_symlinks: bool) -> &mut Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
 
-        if n != len {
-            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "The space is not enough."));
-        }
+    /// Whether to verify the log file reopened after a rotation, for network file systems on which the handle may still point to the rotated copy. If it is `true`, the handle of the reopened log file has to be the file at the path of the log file in the append mode, other than the rotated log file; after a renaming the log file is created without truncating what another writer may have written meanwhile, and after a copy it is truncated and has to be empty; otherwise it is reopened once more, and a `ReopenMismatch` is raised through `PipeLogger::set_reopen_mismatch_callback`. The rotation fails if the second handle does not pass either.
+    pub fn set_verify_reopen(&mut self, verify_reopen: bool) -> &mut Self {
+        self.verify_reopen = verify_reopen;
+        self
+    }
 
-        self.file = Some(file);
+    /// The field identifiers (e.g. `date`, `time`, `c-ip`, `cs-method`) of the W3C extended log format. If it is set, every new log file begins with the `#Version`, `#Fields` and `#Date` directives, and entries can be written by `PipeLogger::write_w3c`.
+    pub fn set_w3c_fields(&mut self, w3c_fields: Option<Vec<String>>) -> &mut Self {
+        self.w3c_fields = w3c_fields;
+        self
+    }
 
-        Ok(new_file)
+    pub fn set_tee(&mut self, tee: Option<Tee>) -> &mut Self {
+        self.tee = tee;
+        self
     }
 
-    /// Write a string with a new line. If the log is rotated, this method returns the renamed path.
-    pub fn write_line<S: AsRef<str>>(&mut self, text: S) -> io::Result<Option<PathBuf>> {
-        let new_file = self.write(text)?;
+    /// The maximum number of pending writes to the tee output. If it is set, the tee output is written in a background thread, and writes are dropped instead of blocking the logger when the buffer is full (e.g. stdout is piped to a slow consumer).
+    pub fn set_tee_buffer(&mut self, tee_buffer: Option<usize>) -> &mut Self {
+        self.tee_buffer = tee_buffer;
+        self
+    }
 
-        if new_file.is_none() {
-            match self.file {
-                Some(ref mut file) => {
-                    let n = file.write(b"\n")?;
-
-                    if n != 1 {
-                        return Err(io::Error::new(
-                            io::ErrorKind::BrokenPipe,
-                            "The space is not enough.",
-                        ));
-                    }
+    /// A writer (e.g. a socket, another file or a buffer in tests) receiving a copy of the log output, in addition to the tee output. It is written synchronously after the log file even if `tee_buffer` is set, and it is shared by the clones of this builder and all of the loggers built from them, including their children. A failed write is counted in `PipeLogger::extra_sink_error_count` and passed to `PipeLogger::set_sink_error_callback`, and does not affect writing the log file.
+    pub fn set_extra_sink(&mut self, extra_sink: Option<Box<dyn Write + Send>>) -> &mut Self {
+        self.extra_sink = extra_sink.map(ExtraSink::new);
+        self
+    }
 
-                    self.file_size += 1u64;
-                },
-                None => unreachable!(),
-            }
-            self.print("\n");
-        }
+    /// The interval of the heartbeat lines (e.g. `alive, 1234 lines, 5 rotations`). If it is set, the global logger installed by `init` writes a heartbeat line from a background thread at this interval, even if nothing else is written. Other loggers can write one by `PipeLogger::write_heartbeat`.
+    pub fn set_heartbeat_interval(&mut self, heartbeat_interval: Option<Duration>) -> &mut Self {
+        self.heartbeat_interval = heartbeat_interval;
+        self
+    }
 
-        Ok(new_file)
+    /// A line written to the log file when the logger is closed, such as at the end of the input, so that a complete log can be told from a truncated one.
+    pub fn set_final_marker(&mut self, final_marker: Option<String>) -> &mut Self {
+        self.final_marker = final_marker;
+        self
     }
 
-    fn print<S: AsRef<str>>(&self, text: S) {
-        let s = text.as_ref();
+    /// Rotate (and compress) the log file when the logger is closed, so that every run yields a sealed rotated log file. Closing waits for its compression until the deadline.
+    pub fn set_rotate_on_close(&mut self, rotate_on_close: bool) -> &mut Self {
+        self.rotate_on_close = rotate_on_close;
+        self
+    }
 
-        match &self.tee {
-            Some(tee) => match tee {
-                Tee::Stdout => {
-                    print!("{}", s);
+    /// Whether to append a sequence number within the millisecond (e.g. `mylog-2024-01-02-03-04-05-678-001.txt`) to the names of the rotated log files. If it is `true`, rotations in the same millisecond get increasing sequence numbers instead of waiting for the next millisecond, and the names keep the rotation order even if the clock goes backwards.
+    pub fn set_rotation_sequence(&mut self, rotation_sequence: bool) -> &mut Self {
+        self.rotation_sequence = rotation_sequence;
+        self
+    }
+
+    /// How to split the file name of the log file into the stem and the extension, which decides the names of the rotated log files and which files are recognized as them. It is `NameSplit::LastDot` by default.
+    pub fn set_name_split(&mut self, name_split: NameSplit) -> &mut Self {
+        self.name_split = name_split;
+        self
+    }
+
+    /// How to name the rotated log files. It is `RotationNaming::Timestamp` by default. The rotation sequence, the timestamp precision, `min_valid_time` and the deterministic advance of the timestamps only apply to `RotationNaming::Timestamp`, and `PipeLogSearcher` and `migrate_naming` only recognize it.
+    pub fn set_rotation_naming(&mut self, rotation_naming: RotationNaming) -> &mut Self {
+        self.rotation_naming = rotation_naming;
+        self
+    }
+
+    /// The precision of the timestamps in the names of the rotated log files. It is `TimestampPrecision::Millis` by default. Rotated log files named in any precision are recognized regardless of this setting. Unless the rotation sequence is enabled, a rotation waits for the next second in `TimestampPrecision::Seconds` if the previous rotation was done in the same second.
+    pub fn set_timestamp_precision(
+        &mut self,
+        timestamp_precision: TimestampPrecision,
+    ) -> &mut Self {
+        self.timestamp_precision = timestamp_precision;
+        self
+    }
+
+    /// Whether the timestamps in the names of the rotated log files are in local time rather than UTC. The names in local time may be ordered wrongly around the end of daylight saving time.
+    pub fn set_local_time(&mut self, local_time: bool) -> &mut Self {
+        self.local_time = local_time;
+        self
+    }
+
+    /// Whether to do everything on the thread which writes to the logger, mainly for tests. If it is `true`, the rotated log files are processed (e.g. compressed) before `write` returns, `tee_buffer` and the heartbeat thread of the global logger are ignored, and a rotation never waits for the clock to advance; the timestamp in the name is advanced by one unit of the precision instead.
+    pub fn set_deterministic(&mut self, deterministic: bool) -> &mut Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// The function to get the current time, which is used to name the rotated log files and to write the W3C `#Date` directive. It is `Utc::now` by default, and can be replaced with a fixed clock in tests.
+    pub fn set_clock(&mut self, clock: fn() -> DateTime<Utc>) -> &mut Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The earliest time which the clock is trusted to be synced at, for devices which boot with their clocks reset (e.g. to 1970-01-01) until the time is synced. While the clock is earlier than this, the rotated log files are named after the newest rotated log file (or this time if there is none) with increasing sequence numbers, so that they are still ordered after the existing ones.
+    pub fn set_min_valid_time(&mut self, min_valid_time: Option<DateTime<Utc>>) -> &mut Self {
+        self.min_valid_time = min_valid_time;
+        self
+    }
+
+    /// Convert this builder into one which owns its log path, so that it can be stored without the lifetime of a borrowed path.
+    pub fn into_owned(self) -> PipeLoggerBuilder<PathBuf> {
+        PipeLoggerBuilder {
+            rotate:              self.rotate,
+            count:               self.count,
+            thinning:            self.thinning,
+            max_age:             self.max_age,
+            max_total_size:      self.max_total_size,
+            compaction_size:     self.compaction_size,
+            background_removal:  self.background_removal,
+            min_file_age:        self.min_file_age,
+            max_backlog_growth:  self.max_backlog_growth,
+            rotate_on_build:     self.rotate_on_build,
+            retention_exemption: self.retention_exemption,
+            log_path:            self.log_path.as_ref().to_path_buf(),
+            compress:            self.compress,
+            compression_threads: self.compression_threads,
+            compression_tail:    self.compression_tail,
+            compression_level:   self.compression_level,
+            compression_verify:  self.compression_verify,
+            compress_method:     self.compress_method,
+            processors:          self.processors,
+            processor_retries:   self.processor_retries,
+            retry_delay:         self.retry_delay,
+            dead_letter_dir:     self.dead_letter_dir,
+            external_rotation:   self.external_rotation,
+            reopen_on_move:      self.reopen_on_move,
+            lock:                self.lock,
+            file_mode:           self.file_mode,
+            follow_symlinks:     self.follow_symlinks,
+            verify_reopen:       self.verify_reopen,
+            w3c_fields:          self.w3c_fields,
+            tee:                 self.tee,
+            tee_buffer:          self.tee_buffer,
+            extra_sink:          self.extra_sink,
+            heartbeat_interval:  self.heartbeat_interval,
+            final_marker:        self.final_marker,
+            rotate_on_close:     self.rotate_on_close,
+            rotation_sequence:   self.rotation_sequence,
+            name_split:          self.name_split,
+            rotation_naming:     self.rotation_naming,
+            timestamp_precision: self.timestamp_precision,
+            local_time:          self.local_time,
+            deterministic:       self.deterministic,
+            clock:               self.clock,
+            min_valid_time:      self.min_valid_time,
+        }
+    }
+
+    /// Build a new PipeLogger.
+    #[inline]
+    pub fn build(self) -> Result<PipeLogger, PipeLoggerBuilderError> {
+        self.build_with_rotated_log_file_names(None)
+    }
+
+    /// Build a new PipeLogger. If the names of the rotated log files are known because a logger has been built for the same log file in this process, the directory is not scanned, and the files left by a crashed rotation are not recovered again.
+    pub(crate) fn build_with_rotated_log_file_names(
+        self,
+        rotated_log_file_names: Option<Vec<String>>,
+    ) -> Result<PipeLogger, PipeLoggerBuilderError> {
+        if let Err(reason) = self.rotation_naming.pattern() {
+            return Err(PipeLoggerBuilderError::InvalidRotationNaming(reason));
+        }
+
+        if self.rotation_naming == RotationNaming::Sequential
+            && (self.thinning.is_some() || self.max_age.is_some())
+        {
+            return Err(PipeLoggerBuilderError::InvalidRotationNaming(
+                "The thinning and `max_age` need the timestamps in the names of the rotated log \
+                 files, which `RotationNaming::Sequential` does not have."
+                    .to_string(),
+            ));
+        }
+
+        if self.rotation_naming == RotationNaming::Sequential
+            && (self.max_total_size.is_some() || self.compaction_size.is_some())
+        {
+            return Err(PipeLoggerBuilderError::InvalidRotationNaming(
+                "`max_total_size` and `compaction_size` need the sizes of the rotated log files \
+                 at their names, which `RotationNaming::Sequential` shifts in the background."
+                    .to_string(),
+            ));
+        }
+
+        if let Some(level) = self.compression_level.find_out_of_range(self.compress_method) {
+            return Err(PipeLoggerBuilderError::InvalidCompressionLevel(level));
+        }
+
+        if let Some(rotate) = &self.rotate {
+            if let Some(file_size) = rotate.file_size() {
+                if file_size < 2 {
+                    return Err(PipeLoggerBuilderError::RotateFileSizeTooSmall);
+                }
+            }
+
+            if let Some(interval) = rotate.interval() {
+                if interval.is_zero() {
+                    return Err(PipeLoggerBuilderError::RotateIntervalTooShort);
+                }
+            }
+
+            if let Some(count) = &self.count {
+                if *count < 1 {
+                    return Err(PipeLoggerBuilderError::CountTooSmall);
+                }
+            }
+        }
+
+        let file_path = self.log_path.as_ref().absolutize()?.into_owned();
+
+        #[cfg(windows)]
+        let file_path = to_extended_length_path(file_path);
+
+        // a root directory has no parent
+        let folder_path = match file_path.parent() {
+            Some(parent) => parent.to_path_buf(),
+            None => return Err(PipeLoggerBuilderError::FileIsDirectory(file_path)),
+        };
+
+        let file_name = match file_path.file_name() {
+            Some(file_name) => file_name.to_string_lossy().into_owned(),
+            None => return Err(PipeLoggerBuilderError::FileIsDirectory(file_path)),
+        };
+
+        // all of the options are validated before touching the file system
+        let file_name_point_index = self.name_split.split_index(&file_name)?;
+
+        // rely on the result of opening the file instead of checking it in advance, to avoid TOCTOU races
+        let mut file =
+            match open_log_file(&file_path, OpenMode::Append, self.lock, self.follow_symlinks) {
+                Ok(file) => file,
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    return Err(PipeLoggerBuilderError::AlreadyLocked(file_path));
                 },
-                Tee::Stderr => {
-                    eprint!("{}", s);
+                Err(err) => {
+                    // just for reporting the error, not a check before opening
+                    return if fs::metadata(&file_path).map(|m| m.is_dir()).unwrap_or(false) {
+                        Err(PipeLoggerBuilderError::FileIsDirectory(file_path))
+                    } else {
+                        Err(PipeLoggerBuilderError::IOError(err))
+                    };
                 },
+            };
+
+        let metadata = file.metadata()?;
+
+        let modified_time = metadata.modified().ok().map(DateTime::<Utc>::from);
+
+        if metadata.is_dir() {
+            return Err(PipeLoggerBuilderError::FileIsDirectory(file_path));
+        }
+
+        let mut file_size = metadata.len();
+
+        if file_size == 0 {
+            if let Some(w3c_fields) = &self.w3c_fields {
+                file_size += write_w3c_header(&mut file, w3c_fields, (self.clock)())?;
+            }
+        }
+
+        #[cfg(unix)]
+        if let Some(file_mode) = self.file_mode {
+            use std::os::unix::fs::PermissionsExt;
+
+            file.set_permissions(fs::Permissions::from_mode(file_mode))?;
+        }
+
+        let (rotated_file_name, rotated_file_name_point_index) =
+            rotated_base_name(&file_name, file_name_point_index);
+
+        let (rotated_stem, rotated_ext) =
+            self.rotation_naming.split(&rotated_file_name, rotated_file_name_point_index);
+
+        let scanned = rotated_log_file_names.is_none();
+
+        let rotated_log_file_names = match rotated_log_file_names {
+            Some(rotated_log_file_names) => rotated_log_file_names,
+            None => scan_rotated_log_file_names(
+                &folder_path,
+                &rotated_file_name,
+                rotated_file_name_point_index,
+                &self.rotation_naming,
+            )?,
+        };
+
+        let mut logger = PipeLogger {
+            rotate: self.rotate,
+            count: self.count,
+            thinning: self.thinning,
+            max_age: self.max_age,
+            max_total_size: self.max_total_size,
+            compaction_size: self.compaction_size,
+            background_removal: self.background_removal,
+            min_file_age: self.min_file_age,
+            max_backlog_growth: self.max_backlog_growth,
+            rotate_on_build: self.rotate_on_build,
+            file_opened_time: Instant::now(),
+            next_rotation_time: self.rotate.and_then(|rotate| {
+                // a log file left by the last run is rotated after the threshold of the time it was last modified
+                let since = match modified_time {
+                    Some(modified_time) if metadata.len() > 0 => modified_time,
+                    _ => (self.clock)(),
+                };
+
+                rotate.next_rotation_time(since, self.local_time)
+            }),
+            retention_exemption: self.retention_exemption,
+            pinned_log_file_names: Vec::new(),
+            next_rotation_meta: BTreeMap::new(),
+            file: Some(file),
+            file_name,
+            file_name_point_index,
+            rotated_stem: rotated_stem.to_string(),
+            rotated_ext: rotated_ext.to_string(),
+            file_path,
+            file_size,
+            folder_path,
+            rotated_log_file_names,
+            compress: self.compress,
+            compression_threads: self.compression_threads,
+            compression_tail: self.compression_tail,
+            compression_level: self.compression_level,
+            compression_verify: self.compression_verify,
+            compress_method: self.compress_method,
+            processors: self.processors,
+            processor_retries: self.processor_retries,
+            retry_delay: self.retry_delay,
+            dead_letter_dir: self.dead_letter_dir,
+            dead_letter_callback: None,
+            external_rotation: self.external_rotation,
+            reopen_on_move: self.reopen_on_move,
+            lock: self.lock,
+            file_mode: self.file_mode,
+            follow_symlinks: self.follow_symlinks,
+            verify_reopen: self.verify_reopen,
+            w3c_fields: self.w3c_fields,
+            tee_buffer: match (&self.tee, self.tee_buffer) {
+                (Some(tee), Some(capacity)) if !self.deterministic => {
+                    Some(TeeBuffer::new(tee.clone(), capacity))
+                },
+                _ => None,
+            },
+            tee: self.tee,
+            extra_sink: self.extra_sink,
+            sink_error_callback: None,
+            tee_error_count: 0,
+            tee_dropped_count: 0,
+            extra_sink_error_count: 0,
+            heartbeat_interval: self.heartbeat_interval,
+            final_marker: self.final_marker,
+            rotate_on_close: self.rotate_on_close,
+            rotation_sequence: self.rotation_sequence,
+            name_split: self.name_split,
+            rotation_naming: self.rotation_naming,
+            timestamp_precision: self.timestamp_precision,
+            local_time: self.local_time,
+            deterministic: self.deterministic,
+            clock: self.clock,
+            min_valid_time: self.min_valid_time,
+            line_count: 0,
+            rotation_count: 0,
+            rate_counter: RateCounter::new(),
+            volume_monitor: None,
+            reopen_callback: None,
+            group: None,
+            last_rotated_time: 0,
+            last_rotated_sequence: 0,
+            unsynced_size: 0,
+            compressions: Vec::new(),
+            worker: BackgroundWorker::new(),
+        };
+
+        if scanned {
+            logger.recover()?;
+        }
+
+        if self.rotate_on_build {
+            if let Some(size) = logger.rotate.and_then(|rotate| rotate.file_size()) {
+                if logger.file_size >= size {
+                    logger.rotate_file(modified_time)?;
+                }
+            }
+        }
+
+        Ok(logger)
+    }
+}
+
+impl<P: AsRef<Path> + Default> Default for PipeLoggerBuilder<P> {
+    #[inline]
+    fn default() -> Self {
+        PipeLoggerBuilder::new(P::default())
+    }
+}
+
+impl<P: AsRef<Path> + PartialEq> PartialEq for PipeLoggerBuilder<P> {
+    /// Regular expressions are compared by their patterns, processors by their identities, and clocks by their addresses.
+    fn eq(&self, other: &Self) -> bool {
+        self.rotate == other.rotate
+            && self.count == other.count
+            && self.thinning == other.thinning
+            && self.max_age == other.max_age
+            && self.max_total_size == other.max_total_size
+            && self.compaction_size == other.compaction_size
+            && self.background_removal == other.background_removal
+            && self.min_file_age == other.min_file_age
+            && self.max_backlog_growth == other.max_backlog_growth
+            && self.rotate_on_build == other.rotate_on_build
+            && self.retention_exemption.as_ref().map(Regex::as_str)
+                == other.retention_exemption.as_ref().map(Regex::as_str)
+            && self.log_path == other.log_path
+            && self.compress == other.compress
+            && self.compression_threads == other.compression_threads
+            && self.compression_tail == other.compression_tail
+            && self.compression_level == other.compression_level
+            && self.compression_verify == other.compression_verify
+            && self.compress_method == other.compress_method
+            && self.processors.len() == other.processors.len()
+            && self.processors.iter().zip(other.processors.iter()).all(|(a, b)| Arc::ptr_eq(a, b))
+            && self.processor_retries == other.processor_retries
+            && self.retry_delay == other.retry_delay
+            && self.dead_letter_dir == other.dead_letter_dir
+            && self.external_rotation == other.external_rotation
+            && self.reopen_on_move == other.reopen_on_move
+            && self.lock == other.lock
+            && self.file_mode == other.file_mode
+            && self.follow_symlinks == other.follow_symlinks
+            && self.verify_reopen == other.verify_reopen
+            && self.w3c_fields == other.w3c_fields
+            && self.tee == other.tee
+            && self.tee_buffer == other.tee_buffer
+            && self.extra_sink == other.extra_sink
+            && self.heartbeat_interval == other.heartbeat_interval
+            && self.final_marker == other.final_marker
+            && self.rotate_on_close == other.rotate_on_close
+            && self.rotation_sequence == other.rotation_sequence
+            && self.name_split == other.name_split
+            && self.rotation_naming == other.rotation_naming
+            && self.timestamp_precision == other.timestamp_precision
+            && self.local_time == other.local_time
+            && self.deterministic == other.deterministic
+            && self.clock as usize == other.clock as usize
+            && self.min_valid_time == other.min_valid_time
+    }
+}
+
+// TODO -----PipeLoggerBuilder END-----
+
+// TODO -----PipeLogger START-----
+
+/// PipeLogger can help you stores, rotates and compresses logs.
+pub struct PipeLogger {
+    rotate:                 Option<RotateMethod>,
+    count:                  Option<usize>,
+    thinning:               Option<Thinning>,
+    max_age:                Option<Duration>,
+    max_total_size:         Option<u64>,
+    compaction_size:        Option<u64>,
+    background_removal:     bool,
+    min_file_age:           Duration,
+    max_backlog_growth:     u64,
+    rotate_on_build:        bool,
+    file_opened_time:       Instant,
+    next_rotation_time:     Option<DateTime<Utc>>,
+    retention_exemption:    Option<Regex>,
+    pinned_log_file_names:  Vec<String>,
+    next_rotation_meta:     BTreeMap<String, String>,
+    file:                   Option<File>,
+    file_name:              String,
+    file_name_point_index:  usize,
+    rotated_stem:           String,
+    rotated_ext:            String,
+    file_path:              PathBuf,
+    file_size:              u64,
+    folder_path:            PathBuf,
+    rotated_log_file_names: Vec<String>,
+    compress:               bool,
+    compression_threads:    usize,
+    compression_tail:       Option<u64>,
+    compression_level:      CompressionLevel,
+    compression_verify:     bool,
+    compress_method:        CompressionMethod,
+    processors:             Vec<Arc<dyn RotationProcessor>>,
+    processor_retries:      u32,
+    retry_delay:            Duration,
+    dead_letter_dir:        Option<PathBuf>,
+    dead_letter_callback:   Option<Arc<DeadLetterCallback>>,
+    external_rotation:      bool,
+    reopen_on_move:         bool,
+    lock:                   bool,
+    file_mode:              Option<u32>,
+    follow_symlinks:        bool,
+    verify_reopen:          bool,
+    reopen_callback:        Option<ReopenMismatchCallback>,
+    w3c_fields:             Option<Vec<String>>,
+    tee:                    Option<Tee>,
+    tee_buffer:             Option<TeeBuffer>,
+    extra_sink:             Option<ExtraSink>,
+    sink_error_callback:    Option<SinkErrorCallback>,
+    tee_error_count:        u64,
+    tee_dropped_count:      u64,
+    extra_sink_error_count: u64,
+    heartbeat_interval:     Option<Duration>,
+    final_marker:           Option<String>,
+    rotate_on_close:        bool,
+    rotation_sequence:      bool,
+    name_split:             NameSplit,
+    rotation_naming:        RotationNaming,
+    timestamp_precision:    TimestampPrecision,
+    local_time:             bool,
+    deterministic:          bool,
+    clock:                  fn() -> DateTime<Utc>,
+    min_valid_time:         Option<DateTime<Utc>>,
+    line_count:             u64,
+    rotation_count:         u64,
+    rate_counter:           RateCounter,
+    volume_monitor:         Option<VolumeMonitor>,
+    group:                  Option<Vec<u8>>,
+    last_rotated_time:      i64,
+    last_rotated_sequence:  u32,
+    unsynced_size:          u64,
+    compressions:           Vec<(PathBuf, u64)>,
+    worker:                 BackgroundWorker,
+}
+
+impl Write for PipeLogger {
+    /// Write UTF-8 data.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        PipeLogger::write(self, String::from_utf8_lossy(buf))?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(extra_sink) = &self.extra_sink {
+            if let Err(err) = extra_sink.flush() {
+                self.sink_error(&err);
+            }
+        }
+
+        self.log_file()?.flush()
+    }
+}
+
+impl fmt::Write for PipeLogger {
+    /// Write a string, so that this logger can be the target of the `write!` and `writeln!` macros without the UTF-8 conversion.
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        PipeLogger::write(self, s).map(|_| ()).map_err(|_| FmtError)
+    }
+}
+
+impl PipeLogger {
+    /// Create a new PipeLoggerBuilder.
+    pub fn builder<P: AsRef<Path>>(log_path: P) -> PipeLoggerBuilder<P> {
+        PipeLoggerBuilder::new(log_path)
+    }
+
+    /// Create a child logger which writes to a derived path (e.g. `mylog-name.txt` for `mylog.txt`) in the same directory and inherits the settings of this logger.
+    #[inline]
+    pub fn child<S: AsRef<str>>(&self, name: S) -> Result<PipeLogger, PipeLoggerBuilderError> {
+        self.child_builder(name).build()
+    }
+
+    /// Create a builder for the child logger of a name.
+    pub(crate) fn child_builder<S: AsRef<str>>(&self, name: S) -> PipeLoggerBuilder<PathBuf> {
+        let stem = format!("{}-{}", &self.file_name[..self.file_name_point_index], name.as_ref());
+        let ext = self.file_name[self.file_name_point_index..].to_string();
+
+        let mut builder =
+            self.to_builder(Path::join(&self.folder_path, format!("{}{}", stem, ext)));
+
+        builder.set_name_split(NameSplit::Explicit {
+            stem,
+            ext,
+        });
+
+        builder
+    }
+
+    /// Check the health of a log file with the settings of a builder, without building a logger or creating the log file. To check a log path with the default settings, pass `&PipeLogger::builder(log_path)`.
+    pub fn doctor<P: AsRef<Path>>(builder: &PipeLoggerBuilder<P>) -> DoctorReport {
+        doctor::diagnose(builder)
+    }
+
+    /// Create a builder with the settings of this logger.
+    fn to_builder<P: AsRef<Path>>(&self, log_path: P) -> PipeLoggerBuilder<P> {
+        let mut builder = PipeLoggerBuilder::new(log_path);
+
+        builder
+            .set_rotate(self.rotate)
+            .set_count(self.count)
+            .set_thinning(self.thinning)
+            .set_max_age(self.max_age)
+            .set_max_total_size(self.max_total_size)
+            .set_compaction_size(self.compaction_size)
+            .set_background_retention(self.background_removal)
+            .set_min_file_age_before_rotation(self.min_file_age)
+            .set_max_backlog_growth(self.max_backlog_growth)
+            .set_rotate_on_build(self.rotate_on_build)
+            .set_retention_exemption(self.retention_exemption.clone())
+            .set_compress(self.compress)
+            .set_compression_threads(self.compression_threads)
+            .set_compression_tail(self.compression_tail)
+            .set_compression_level(self.compression_level)
+            .set_compression_verify(self.compression_verify)
+            .set_compress_method(self.compress_method)
+            .set_processors(self.processors.clone())
+            .set_processor_retries(self.processor_retries)
+            .set_retry_delay(self.retry_delay)
+            .set_dead_letter_dir(self.dead_letter_dir.clone())
+            .set_external_rotation(self.external_rotation)
+            .set_reopen_on_move(self.reopen_on_move)
+            .set_lock(self.lock)
+            .set_file_mode(self.file_mode)
+            .set_follow_symlinks(self.follow_symlinks)
+            .set_verify_reopen(self.verify_reopen)
+            .set_w3c_fields(self.w3c_fields.clone())
+            .set_tee(self.tee.clone())
+            .set_tee_buffer(self.tee_buffer.as_ref().map(|tee_buffer| tee_buffer.capacity()))
+            .set_heartbeat_interval(self.heartbeat_interval)
+            .set_final_marker(self.final_marker.clone())
+            .set_rotate_on_close(self.rotate_on_close)
+            .set_rotation_sequence(self.rotation_sequence)
+            .set_name_split(self.name_split.clone())
+            .set_rotation_naming(self.rotation_naming.clone())
+            .set_timestamp_precision(self.timestamp_precision)
+            .set_local_time(self.local_time)
+            .set_deterministic(self.deterministic)
+            .set_clock(self.clock)
+            .set_min_valid_time(self.min_valid_time);
+
+        builder.extra_sink = self.extra_sink.clone();
+
+        builder
+    }
+
+    /// Write a string. If the log is rotated, this method returns the renamed path.
+    pub fn write<S: AsRef<str>>(&mut self, text: S) -> io::Result<Option<PathBuf>> {
+        let buf = text.as_ref().as_bytes();
+
+        if buf.is_empty() {
+            return Ok(None);
+        }
+
+        if let Some(group) = self.group.as_mut() {
+            group.extend_from_slice(buf);
+
+            return Ok(None);
+        }
+
+        self.record_rate(count_lines(buf), buf.len() as u64);
+
+        let result = self.write_bytes(buf);
+
+        self.print(&[buf]);
+
+        result
+    }
+
+    /// Start buffering the following writes in memory until `commit_group` is called, so that they are written to the log file all at once. It does nothing if a group has been started. A group which is not committed is discarded when this logger is dropped.
+    pub fn begin_group(&mut self) {
+        if self.group.is_none() {
+            self.group = Some(Vec::new());
+        }
+    }
+
+    /// Write the buffered group with a single write, so that it is never split across rotated log files. If the log is rotated, this method returns the renamed path.
+    pub fn commit_group(&mut self) -> io::Result<Option<PathBuf>> {
+        match self.group.take() {
+            Some(group) if !group.is_empty() => {
+                self.record_rate(count_lines(&group), group.len() as u64);
+
+                let result = self.write_bytes(&group);
+
+                self.print(&[&group]);
+
+                result
             },
-            None => (),
+            _ => Ok(None),
+        }
+    }
+
+    /// Write some bytes to the log file, and rotate it if needed. The tee output is not written.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(len = buf.len()))
+    )]
+    fn write_bytes(&mut self, buf: &[u8]) -> io::Result<Option<PathBuf>> {
+        let len = buf.len();
+
+        if len == 0 {
+            return Ok(None);
+        }
+
+        let mut new_file = self.rotate_by_time()?;
+
+        if self.reopen_on_move && self.is_log_file_moved()? {
+            self.reopen_log_file()?;
+        }
+
+        if self.external_rotation {
+            let file_size = self.log_file()?.metadata()?.len();
+
+            if file_size < self.file_size {
+                // the log file has been truncated by somebody else
+                self.file_size = file_size;
+
+                if file_size == 0 {
+                    self.write_w3c_directives()?;
+                }
+            }
+        }
+
+        let n = self.log_file()?.write(buf)?;
+
+        self.file_size += n as u64;
+        self.unsynced_size += n as u64;
+
+        if let Some(size) = self.rotate.and_then(|rotate| rotate.file_size()) {
+            if !self.external_rotation
+                && self.file_size >= size
+                && self.file_opened_time.elapsed() >= self.min_file_age
+                && self.file_size >= self.effective_rotation_size(size)
+            {
+                new_file = Some(self.rotate_file(None)?);
+            }
+        }
+
+        if n != len {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "The space is not enough."));
+        }
+
+        Ok(new_file)
+    }
+
+    /// Rotate the log file if the time threshold of the rotate method has passed, so that the data written after it belongs to the next log file. An empty log file is not rotated.
+    fn rotate_by_time(&mut self) -> io::Result<Option<PathBuf>> {
+        match self.next_rotation_time {
+            Some(next_rotation_time)
+                if !self.external_rotation && (self.clock)() >= next_rotation_time =>
+            {
+                if self.file_size > 0 {
+                    self.rotate()
+                } else {
+                    // an empty log file is kept for the next period
+                    self.next_rotation_time = self.rotate.and_then(|rotate| {
+                        rotate.next_rotation_time((self.clock)(), self.local_time)
+                    });
+
+                    Ok(None)
+                }
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Grow the rotation size by the number of rotated log files still being processed, up to `max_backlog_growth` times.
+    fn effective_rotation_size(&mut self, size: u64) -> u64 {
+        if self.max_backlog_growth <= 1 {
+            return size;
+        }
+
+        self.compressions.retain(|(_, job)| !self.worker.is_finished(*job));
+
+        size.saturating_mul((self.compressions.len() as u64 + 1).min(self.max_backlog_growth))
+    }
+
+    /// Rotate the log file now, regardless of its size. Nothing is done if the log file is empty. Returns the renamed path.
+    pub fn rotate(&mut self) -> io::Result<Option<PathBuf>> {
+        if self.file_size == 0 {
+            return Ok(None);
+        }
+
+        self.rotate_file(None).map(Some)
+    }
+
+    /// Reopen (or create) the log file at its path, e.g. after it is moved or deleted by logrotate. It can be called from the handler of `SIGHUP`. The data written before is synced to the file opened before.
+    pub fn reopen(&mut self) -> io::Result<()> {
+        self.reopen_log_file()
+    }
+
+    /// Sync the current handle, and replace it with the log file opened at its path again. The current handle is kept if the log file cannot be opened.
+    fn reopen_log_file(&mut self) -> io::Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            file.flush()?;
+            file.sync_all()?;
+        }
+
+        self.unsynced_size = 0;
+
+        let file = self.open_appending_log_file()?;
+
+        self.file = Some(file);
+
+        self.next_rotation_time = self
+            .rotate
+            .and_then(|rotate| rotate.next_rotation_time((self.clock)(), self.local_time));
+
+        Ok(())
+    }
+
+    /// Open the log file at its path for appending, and begin it with the W3C directives if it is empty.
+    fn open_appending_log_file(&mut self) -> io::Result<File> {
+        let mut file =
+            open_log_file(&self.file_path, OpenMode::Append, self.lock, self.follow_symlinks)?;
+
+        self.file_size = file.metadata()?.len();
+
+        if self.file_size == 0 {
+            if let Some(w3c_fields) = &self.w3c_fields {
+                let n = write_w3c_header(&mut file, w3c_fields, (self.clock)())?;
+
+                self.file_size += n;
+                self.unsynced_size += n;
+            }
+        }
+
+        self.file_opened_time = Instant::now();
+
+        Ok(file)
+    }
+
+    /// The handle of the log file. If it has been lost because a rotation failed after closing it, the log file is opened again.
+    fn log_file(&mut self) -> io::Result<&mut File> {
+        let file = match self.file.take() {
+            Some(file) => file,
+            None => self.open_appending_log_file()?,
+        };
+
+        Ok(self.file.insert(file))
+    }
+
+    /// Whether the path of the log file no longer refers to the handle. A lost handle is regarded as moved.
+    fn is_log_file_moved(&self) -> io::Result<bool> {
+        match self.file.as_ref() {
+            Some(file) => is_log_file_moved(&self.file_path, file),
+            None => Ok(true),
+        }
+    }
+
+    /// Write the W3C directives to the log file if the fields of the W3C extended log format are set.
+    fn write_w3c_directives(&mut self) -> io::Result<()> {
+        let date = (self.clock)();
+
+        if let (Some(w3c_fields), Some(file)) = (self.w3c_fields.as_ref(), self.file.as_mut()) {
+            let n = write_w3c_header(file, w3c_fields, date)?;
+
+            self.file_size += n;
+            self.unsynced_size += n;
+        }
+
+        Ok(())
+    }
+
+    /// Format a time in the names of the rotated log files, in local time if `local_time` is set.
+    #[inline]
+    fn format_time(&self, utc: &DateTime<Utc>, format: &str) -> String {
+        if self.local_time {
+            utc.with_timezone(&Local).format(format).to_string()
+        } else {
+            utc.format(format).to_string()
+        }
+    }
+
+    /// Parse the time in the name of a rotated log file.
+    #[inline]
+    fn parse_rotated_time(&self, rotated_log_file_name: &str) -> Option<DateTime<Utc>> {
+        let varying = rotated_log_file_name
+            .get(self.rotated_stem.len()..rotated_log_file_name.len() - self.rotated_ext.len())?;
+
+        self.rotation_naming.parse_timestamp(varying, self.local_time)
+    }
+
+    /// Let the worker rename the rotated log files named in `RotationNaming::Sequential` to the next indices and then rename the pending rotated log file to the index `1`, after the jobs submitted before, which may be working on the files at their current names. The names are shifted right away, so the jobs submitted after this refer to the files at their new names.
+    fn shift_sequential_log_files(&mut self, pending_log_file: PathBuf) {
+        let folder_path = self.folder_path.clone();
+        let stem = self.rotated_stem.clone();
+        let rotated_log_file_names = self.rotated_log_file_names.clone();
+
+        let shift = move || {
+            if shift_sequential_log_files(
+                &folder_path,
+                &stem,
+                &rotated_log_file_names,
+                &pending_log_file,
+            )
+            .is_err()
+            {
+                // the pending rotated log file is renamed when a logger is built next time
+            }
+        };
+
+        if self.deterministic {
+            shift();
+        } else {
+            self.worker.submit(Box::new(shift));
+        }
+
+        self.shift_sequential_log_file_names();
+    }
+
+    /// Change the names of the rotated log files named in `RotationNaming::Sequential` to the next indices.
+    fn shift_sequential_log_file_names(&mut self) {
+        let stem = self.rotated_stem.as_str();
+
+        for name in
+            self.rotated_log_file_names.iter_mut().chain(self.pinned_log_file_names.iter_mut())
+        {
+            if let Some(next) = next_sequential_log_file_name(stem, name) {
+                *name = next;
+            }
+        }
+    }
+
+    /// If the clock (or `at`) is earlier than `min_valid_time`, returns the tick to name the rotated log file after, which is right after the newest rotated log file.
+    fn unsynced_time(&self, at: Option<DateTime<Utc>>) -> Option<i64> {
+        let min_valid_time = self.min_valid_time?;
+
+        if self.rotation_naming != RotationNaming::Timestamp {
+            return None;
+        }
+
+        if at.unwrap_or_else(self.clock) >= min_valid_time {
+            return None;
+        }
+
+        let precision = self.timestamp_precision;
+
+        let newest = self.rotated_log_file_names.last().and_then(|name| {
+            let newest = precision.tick(&self.parse_rotated_time(name)?);
+
+            // a name with a sequence number is ordered before the same timestamp without one, so the sequence continues only after a name with a sequence number
+            let varying = &name[self.rotated_stem.len()..name.len() - self.rotated_ext.len()];
+
+            // `-<timestamp>-<sequence>` in the configured precision
+            let has_sequence = varying
+                .get(1 + precision.formatted_len()..)
+                .and_then(|sequence| sequence.strip_prefix('-'))
+                .map_or(false, |sequence| {
+                    !sequence.is_empty() && sequence.bytes().all(|b| b.is_ascii_digit())
+                });
+
+            if has_sequence {
+                Some(newest)
+            } else {
+                Some(newest + 1)
+            }
+        });
+
+        Some(newest.unwrap_or(0).max(precision.tick(&min_valid_time)))
+    }
+
+    /// The size of a rotated log file on the disk. A file being compressed is measured by its uncompressed size.
+    fn rotated_log_file_size(&self, rotated_log_file_name: &str) -> u64 {
+        [Path::join(&self.folder_path, rotated_log_file_name)]
+            .into_iter()
+            .chain(compressed_paths(&self.folder_path, rotated_log_file_name))
+            .find_map(|path| fs::metadata(path).ok())
+            .map_or(0, |metadata| metadata.len())
+    }
+
+    /// Reopen the log file for appending and truncate it, and then verify the handle. It is reopened once more if the verification fails.
+    fn reopen_verified_log_file(&self, renamed: bool, rotated_log_file: &Path) -> io::Result<File> {
+        let reopen = || {
+            let file = if renamed {
+                // the log file is created, unless another writer has created it after the renaming, whose content is kept
+                match open_log_file(
+                    &self.file_path,
+                    OpenMode::CreateNew,
+                    self.lock,
+                    self.follow_symlinks,
+                ) {
+                    Ok(file) => file,
+                    Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists => open_log_file(
+                        &self.file_path,
+                        OpenMode::Append,
+                        self.lock,
+                        self.follow_symlinks,
+                    )?,
+                    Err(err) => return Err(err),
+                }
+            } else {
+                // the copied log file is truncated through a handle in the append mode
+                let file = open_log_file(
+                    &self.file_path,
+                    OpenMode::Append,
+                    self.lock,
+                    self.follow_symlinks,
+                )?;
+
+                file.set_len(0)?;
+
+                file
+            };
+
+            let mismatch =
+                check_reopened_log_file(&self.file_path, &file, rotated_log_file, !renamed)?;
+
+            Ok::<_, io::Error>((file, mismatch))
+        };
+
+        let reason = match reopen()? {
+            (file, None) => return Ok(file),
+            (file, Some(reason)) => {
+                drop(file);
+
+                reason
+            },
+        };
+
+        let (file, mismatch) = reopen()?;
+
+        if let Some(callback) = self.reopen_callback.as_ref() {
+            callback(ReopenMismatch {
+                path: self.file_path.clone(),
+                reason,
+                recovered: mismatch.is_none(),
+            });
+        }
+
+        match mismatch {
+            None => Ok(file),
+            Some(reason) => Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "The reopened log file `{}` fails the verification ({:?}).",
+                    self.file_path.to_string_lossy(),
+                    reason
+                ),
+            )),
+        }
+    }
+
+    /// Rename the log file (or copy and truncate it if renaming is not possible), reopen it, process the rotated log file and apply the retention. The rotated log file is named after `at` if it is set and the name is not taken, or the current time. Returns the renamed path. If the rotation fails after the handle of the log file is closed, the log file is opened again, so that this logger keeps working.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(file_size = self.file_size, rotated_log_file = tracing::field::Empty)
+        )
+    )]
+    fn rotate_file(&mut self, mut at: Option<DateTime<Utc>>) -> io::Result<PathBuf> {
+        let stem = self.rotated_stem.clone();
+        let ext = self.rotated_ext.clone();
+
+        // the previous rotation may be done by another logger, so the names are also checked on the disk
+        let is_taken = |rotated_log_file_name: &str| {
+            Path::join(&self.folder_path, rotated_log_file_name).exists()
+                || compressed_paths(&self.folder_path, rotated_log_file_name).any(|p| p.exists())
+        };
+
+        let rotated_log_file_name = match &self.rotation_naming {
+            RotationNaming::Timestamp => {
+                let precision = self.timestamp_precision;
+
+                let unsynced_time = self.unsynced_time(at);
+
+                let timestamp = if self.rotation_sequence || unsynced_time.is_some() {
+                    let tick = match unsynced_time {
+                        Some(tick) => tick,
+                        None => precision.tick(&at.unwrap_or_else(self.clock)),
+                    };
+                    if tick <= self.last_rotated_time {
+                        // in the same tick, or the clock has gone backwards
+                        self.last_rotated_sequence += 1;
+                    } else {
+                        self.last_rotated_time = tick;
+                        self.last_rotated_sequence = 0;
+                    }
+
+                    let datetime = precision.datetime_of_tick(self.last_rotated_time);
+
+                    loop {
+                        let timestamp = format!(
+                            "{}-{:03}",
+                            self.format_time(&datetime, precision.format_str()),
+                            self.last_rotated_sequence
+                        );
+
+                        if !is_taken(&format!("{}-{}{}", stem, timestamp, ext)) {
+                            break timestamp;
+                        }
+
+                        self.last_rotated_sequence += 1;
+                    }
+                } else {
+                    loop {
+                        let mut utc: DateTime<Utc> = at.take().unwrap_or_else(self.clock);
+                        let mut tick = precision.tick(&utc);
+
+                        if self.deterministic && tick <= self.last_rotated_time {
+                            tick = self.last_rotated_time + 1;
+                            utc = precision.datetime_of_tick(tick);
+                        }
+
+                        if self.last_rotated_time != tick {
+                            let timestamp = self.format_time(&utc, precision.format_str());
+
+                            if !is_taken(&format!("{}-{}{}", stem, timestamp, ext)) {
+                                self.last_rotated_time = tick;
+
+                                break timestamp;
+                            }
+
+                            if self.deterministic {
+                                self.last_rotated_time = tick;
+
+                                continue;
+                            }
+                        }
+
+                        // Especially for Windows, because its time precision is about 15ms.
+                        thread::sleep(Duration::from_millis(FILE_WAIT_MILLI_SECONDS));
+                    }
+                };
+
+                format!("{}-{}{}", stem, timestamp, ext)
+            },
+            RotationNaming::Template(template) => {
+                let varying = self.format_time(&at.unwrap_or_else(self.clock), template);
+
+                let mut sequence = 0;
+
+                loop {
+                    let rotated_log_file_name = if sequence == 0 {
+                        format!("{}{}{}", stem, varying, ext)
+                    } else {
+                        format!("{}{}-{:03}{}", stem, varying, sequence, ext)
+                    };
+
+                    if !is_taken(&rotated_log_file_name) {
+                        break rotated_log_file_name;
+                    }
+
+                    sequence += 1;
+                }
+            },
+            RotationNaming::Sequential => format!("{}.1", stem),
+        };
+
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("rotated_log_file", rotated_log_file_name.as_str());
+
+        let rotated_log_file = Path::join(&self.folder_path, Path::new(&rotated_log_file_name));
+
+        // the index `1` is taken until the worker shifts the rotated log files
+        let pending_log_file = if self.rotation_naming == RotationNaming::Sequential {
+            Some(Path::join(
+                &self.folder_path,
+                format!(".{}.{}.pending", self.rotated_stem, self.rotation_count + 1),
+            ))
+        } else {
+            None
+        };
+
+        // the log file is reopened before the rotated log file is processed, which may remove it
+        if let Err(err) =
+            self.rename_and_reopen_log_file(pending_log_file.as_ref().unwrap_or(&rotated_log_file))
+        {
+            // if the handle has been closed, the log file is opened again now, or by the following writes
+            if self.log_file().is_err() {
+                // do nothing
+            }
+
+            return Err(err);
+        }
+
+        self.file_opened_time = Instant::now();
+
+        self.next_rotation_time = self
+            .rotate
+            .and_then(|rotate| rotate.next_rotation_time((self.clock)(), self.local_time));
+
+        self.file_size = 0;
+
+        if let Some(pending_log_file) = pending_log_file {
+            self.shift_sequential_log_files(pending_log_file);
+        }
+
+        self.write_w3c_directives()?;
+
+        let meta = std::mem::take(&mut self.next_rotation_meta);
+
+        if self.compress || !self.processors.is_empty() {
+            self.process_rotated_log_file(&rotated_log_file_name, meta);
+        }
+
+        self.rotated_log_file_names.push(rotated_log_file_name);
+
+        self.rotation_count += 1;
+
+        if self.count.is_some()
+            || self.thinning.is_some()
+            || self.max_age.is_some()
+            || self.max_total_size.is_some()
+        {
+            let retained: Vec<String> = self
+                .rotated_log_file_names
+                .iter()
+                .filter(|name| !self.is_exempt_from_retention(name))
+                .cloned()
+                .collect();
+
+            let mut removed = match &self.thinning {
+                Some(thinning) => thinning.select_removed(
+                    &retained,
+                    |name| self.parse_rotated_time(name),
+                    (self.clock)(),
+                ),
+                None => Vec::new(),
+            };
+
+            if let Some(max_age) = self.max_age.and_then(|age| chrono::Duration::from_std(age).ok())
+            {
+                if let Some(oldest_kept) = (self.clock)().checked_sub_signed(max_age) {
+                    let expired: Vec<String> = retained
+                        .iter()
+                        .filter(|name| {
+                            !removed.contains(name)
+                                && self
+                                    .parse_rotated_time(name)
+                                    .map_or(false, |timestamp| timestamp < oldest_kept)
+                        })
+                        .cloned()
+                        .collect();
+
+                    removed.extend(expired);
+                }
+            }
+
+            if let Some(count) = self.count {
+                let retained: Vec<&String> =
+                    retained.iter().filter(|name| !removed.contains(name)).collect();
+
+                let excess = (retained.len() + 1).saturating_sub(count);
+
+                removed.extend(retained[..excess].iter().map(|name| (*name).clone()));
+            }
+
+            if let Some(max_total_size) = self.max_total_size {
+                let mut total_size: u64 = self
+                    .rotated_log_file_names
+                    .iter()
+                    .filter(|name| !removed.contains(name))
+                    .map(|name| self.rotated_log_file_size(name))
+                    .sum();
+
+                let newest = self.rotated_log_file_names.last();
+
+                let retained: Vec<&String> = retained
+                    .iter()
+                    .filter(|name| !removed.contains(name) && Some(*name) != newest)
+                    .collect();
+
+                for name in retained {
+                    if total_size <= max_total_size {
+                        break;
+                    }
+
+                    total_size = total_size.saturating_sub(self.rotated_log_file_size(name));
+
+                    removed.push(name.clone());
+                }
+            }
+
+            self.rotated_log_file_names.retain(|name| !removed.contains(name));
+
+            if !removed.is_empty() {
+                let folder_path = self.folder_path.clone();
+
+                let remove = move || remove_rotated_log_files(&folder_path, &removed);
+
+                // the rotated log files named in `RotationNaming::Sequential` are at their names after the worker shifts them
+                if (self.background_removal || self.rotation_naming == RotationNaming::Sequential)
+                    && !self.deterministic
+                {
+                    self.worker.submit(Box::new(remove));
+                } else {
+                    remove();
+                }
+            }
+        }
+
+        if let Some(compaction_size) = self.compaction_size {
+            if self.compact_rotated_log_files(compaction_size).is_err() {
+                // the rest of the rotated log files are left uncompacted until the next rotation
+            }
+        }
+
+        let rotated_log_file = if self.compress {
+            let mut s = rotated_log_file.into_os_string();
+            s.push(self.compress_method.extension());
+            PathBuf::from(s)
+        } else {
+            rotated_log_file
+        };
+
+        Ok(rotated_log_file)
+    }
+
+    /// Sync and close the handle of the log file, rename the log file to the rotated log file (or copy it and then truncate the log file if renaming is not possible), and open the log file again. The handle is `None` if this fails after closing it.
+    fn rename_and_reopen_log_file(&mut self, rotated_log_file: &Path) -> io::Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            file.flush()?;
+            file.sync_all()?;
+        }
+
+        self.unsynced_size = 0;
+
+        // an open file cannot be renamed on Windows
+        self.file = None;
+
+        let journal = self.rotation_journal_path();
+
+        let renamed = rename_or_copy(&self.file_path, rotated_log_file, &journal)?;
+
+        if !renamed {
+            copy_file_attributes(&File::open(&self.file_path)?, &File::open(rotated_log_file)?)?;
+        }
+
+        let file = if self.verify_reopen {
+            self.reopen_verified_log_file(renamed, rotated_log_file)?
+        } else {
+            let mode = if renamed { OpenMode::Append } else { OpenMode::Truncate };
+
+            open_log_file(&self.file_path, mode, self.lock, self.follow_symlinks)?
+        };
+
+        let file = self.file.insert(file);
+
+        if renamed {
+            copy_file_attributes(&File::open(rotated_log_file)?, file)?;
+        } else {
+            // the copy has been followed by the truncation
+            fs::remove_file(journal)?;
+        }
+
+        Ok(())
+    }
+
+    /// The path of the journal file which records the rotated log file while the log file is being copied and truncated.
+    #[inline]
+    fn rotation_journal_path(&self) -> PathBuf {
+        Path::join(&self.folder_path, format!(".{}.rotation", self.file_name))
+    }
+
+    /// Write a string with a new line. If the log is rotated, this method returns the renamed path.
+    pub fn write_line<S: AsRef<str>>(&mut self, text: S) -> io::Result<Option<PathBuf>> {
+        let buf = text.as_ref().as_bytes();
+
+        if let Some(group) = self.group.as_mut() {
+            group.extend_from_slice(buf);
+            group.push(b'\n');
+
+            return Ok(None);
+        }
+
+        self.record_rate(count_lines(buf) + 1, buf.len() as u64 + 1);
+
+        let result = self.write_line_bytes(buf);
+
+        self.print(&[buf, b"\n"]);
+
+        result
+    }
+
+    /// Write some bytes with a new line to the log file, and rotate it if needed. The tee output is not written.
+    fn write_line_bytes(&mut self, buf: &[u8]) -> io::Result<Option<PathBuf>> {
+        // the new line is not written after the rotation by size, but it is after the one by time
+        let rotated_log_file = self.rotate_by_time()?;
+
+        let new_file = self.write_bytes(buf)?;
+
+        if new_file.is_none() {
+            let n = self.log_file()?.write(b"\n")?;
+
+            if n != 1 {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "The space is not enough."));
+            }
+
+            self.file_size += 1u64;
+            self.unsynced_size += 1u64;
+        }
+
+        Ok(new_file.or(rotated_log_file))
+    }
+
+    /// Sync the log file and wait for the pending compressions until the deadline, then close this logger. Syncing itself is not interruptible by the deadline.
+    pub fn close(mut self, deadline: Duration) -> ShutdownReport {
+        let start = Instant::now();
+
+        #[cfg(all(feature = "systemd", unix))]
+        sd_notify("STATUS=Syncing the log file").ok();
+
+        let mut finalize_error = None;
+        let mut rotated_log_file = None;
+
+        if let Some(final_marker) = self.final_marker.take() {
+            if let Err(err) = self.write_line(final_marker) {
+                finalize_error = Some(err);
+            }
+        }
+
+        if self.rotate_on_close && finalize_error.is_none() {
+            match self.rotate() {
+                Ok(path) => rotated_log_file = path,
+                Err(err) => finalize_error = Some(err),
+            }
+        }
+
+        let sync_error =
+            self.log_file().and_then(|file| file.flush().and_then(|_| file.sync_all())).err();
+
+        if sync_error.is_none() {
+            self.unsynced_size = 0;
+        }
+
+        self.wait_for_compressions(start, deadline, true);
+
+        if let Some(tee_buffer) = self.tee_buffer.as_mut() {
+            tee_buffer.finish(start, deadline);
+        }
+
+        // the jobs left after the deadline are not waited for when this logger is dropped
+        self.worker.detach();
+
+        ShutdownReport {
+            unsynced_bytes: self.unsynced_size,
+            sync_error,
+            pending_compressions: self.compressions.drain(..).map(|(path, _)| path).collect(),
+            rotated_log_file,
+            finalize_error,
+        }
+    }
+
+    /// Flush and sync the log file, and then wait for the pending compressions until the deadline. Syncing itself is not interruptible by the deadline.
+    pub fn flush_within(&mut self, deadline: Duration) -> io::Result<FlushReport> {
+        let start = Instant::now();
+
+        let file = self.log_file()?;
+
+        file.flush()?;
+        file.sync_all()?;
+
+        self.unsynced_size = 0;
+
+        self.wait_for_compressions(start, deadline, false);
+
+        Ok(FlushReport {
+            pending_compressions: self.compressions.iter().map(|(path, _)| path.clone()).collect(),
+        })
+    }
+
+    /// Wait for the pending jobs of the background worker until the deadline. If `notify` is `true` and the `systemd` feature is enabled, the service manager is asked to extend its shutdown timeout while waiting.
+    fn wait_for_compressions(&mut self, start: Instant, deadline: Duration, notify: bool) {
+        #[cfg(not(all(feature = "systemd", unix)))]
+        let _ = notify;
+
+        #[cfg(all(feature = "systemd", unix))]
+        let mut last_notified: Option<Instant> = None;
+
+        loop {
+            self.compressions.retain(|(_, job)| !self.worker.is_finished(*job));
+
+            let elapsed = start.elapsed();
+
+            if self.worker.is_idle() || elapsed >= deadline {
+                break;
+            }
+
+            #[cfg(all(feature = "systemd", unix))]
+            if notify
+                && last_notified.map(|t| t.elapsed() >= Duration::from_secs(1)).unwrap_or(true)
+            {
+                sd_notify(&format!(
+                    "EXTEND_TIMEOUT_USEC={}\nSTATUS=Compressing {} rotated log file(s)",
+                    (deadline - start.elapsed()).as_micros(),
+                    self.compressions.len()
+                ))
+                .ok();
+
+                last_notified = Some(Instant::now());
+            }
+
+            // wake up every second to extend the shutdown timeout
+            self.worker.wait_idle(Some((deadline - elapsed).min(Duration::from_secs(1))));
+        }
+    }
+
+    /// Wait for all of the pending compressions, the other processing of the rotated log files, and the removals of the retention running in the background.
+    pub fn flush_background(&mut self) {
+        self.worker.wait_idle(None);
+
+        self.compressions.clear();
+    }
+
+    /// Sync the log file and put the active log file and all of the rotated log files into a destination directory, producing a consistent set of files for backup. The active log file is copied and the rotated log files are hard-linked if possible. Returns the paths of the created files.
+    pub fn snapshot<P: AsRef<Path>>(&mut self, dest_dir: P) -> io::Result<Vec<PathBuf>> {
+        let dest_dir = dest_dir.as_ref();
+
+        fs::create_dir_all(dest_dir)?;
+
+        let file = self.log_file()?;
+
+        file.flush()?;
+        file.sync_all()?;
+
+        self.unsynced_size = 0;
+
+        // the rotated log files named in `RotationNaming::Sequential` are at their names after the worker shifts them
+        if self.rotation_naming == RotationNaming::Sequential {
+            self.worker.wait_idle(None);
+        }
+
+        let mut snapshot_files = Vec::with_capacity(self.rotated_log_file_names.len() + 1);
+
+        for rotated_log_file_name in self.rotated_log_file_names.iter() {
+            // prefer the uncompressed one because the compressed one may be still being written
+            let rotated_log_file = Path::join(&self.folder_path, rotated_log_file_name);
+            let snapshot_file = Path::join(dest_dir, rotated_log_file_name);
+
+            match link_or_copy(&rotated_log_file, &snapshot_file) {
+                Ok(_) => {
+                    snapshot_files.push(snapshot_file);
+                    continue;
+                },
+                Err(ref err) if err.kind() == io::ErrorKind::NotFound => (),
+                Err(err) => return Err(err),
+            }
+
+            for rotated_log_file in compressed_paths(&self.folder_path, rotated_log_file_name) {
+                let snapshot_file = Path::join(dest_dir, rotated_log_file.file_name().unwrap());
+
+                match link_or_copy(&rotated_log_file, &snapshot_file) {
+                    Ok(_) => {
+                        snapshot_files.push(snapshot_file);
+                        break;
+                    },
+                    Err(ref err) if err.kind() == io::ErrorKind::NotFound => (),
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+
+        let snapshot_file = Path::join(dest_dir, &self.file_name);
+
+        fs::copy(&self.file_path, &snapshot_file)?;
+
+        snapshot_files.push(snapshot_file);
+
+        Ok(snapshot_files)
+    }
+
+    /// Repair the files left by a process which crashed during a rotation. A rotation renames the active log file to the rotated log file and reopens the active log file, which leaves nothing to repair. If renaming is not possible (e.g. across file systems), the name of the rotated log file is recorded in a journal file, the active log file is copied to a hidden temporary file which is then renamed to the rotated log file, the active log file is truncated, and the journal file is removed. The rotated log file is then compressed into a new file and removed. Therefore,
+    ///
+    /// * if the journal file exists and the rotated log file recorded in it does not, the copy did not finish, so the temporary file is removed and the active log file is kept.
+    /// * if the journal file exists along with the rotated log file recorded in it, the copy finished but the truncation may not, so the active log file is truncated.
+    /// * if a rotated log file named in `RotationNaming::Sequential` is still at its pending name, the shifting did not happen, so it is done now.
+    /// * if a rotated log file still exists along with its compressed file, the compression did not finish, so the compressed file is removed and the compression is restarted if it is enabled.
+    fn recover(&mut self) -> io::Result<()> {
+        self.rotated_log_file_names.dedup();
+
+        let journal = self.rotation_journal_path();
+
+        match fs::read_to_string(&journal) {
+            Ok(rotated_log_file_name) => {
+                let rotated_log_file = Path::join(&self.folder_path, &rotated_log_file_name);
+
+                if rotated_log_file.is_file() {
+                    let file = self.log_file()?;
+
+                    file.set_len(0)?;
+                    file.sync_all()?;
+
+                    self.file_size = 0;
+
+                    self.write_w3c_directives()?;
+                } else {
+                    let copying = copying_path(&rotated_log_file);
+
+                    if copying.is_file() {
+                        fs::remove_file(copying)?;
+                    }
+                }
+
+                fs::remove_file(&journal)?;
+            },
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => return Err(err),
+        }
+
+        if self.rotation_naming == RotationNaming::Sequential {
+            let prefix = format!(".{}.", self.rotated_stem);
+
+            let mut pending_log_files: Vec<(u64, PathBuf)> = self
+                .folder_path
+                .read_dir()?
+                .filter_map(|entry| {
+                    let path = entry.ok()?.path();
+
+                    let rotation = path
+                        .file_name()?
+                        .to_str()?
+                        .strip_prefix(prefix.as_str())?
+                        .strip_suffix(".pending")?
+                        .parse::<u64>()
+                        .ok()?;
+
+                    Some((rotation, path))
+                })
+                .collect();
+
+            pending_log_files.sort();
+
+            for (_, pending_log_file) in pending_log_files {
+                shift_sequential_log_files(
+                    &self.folder_path,
+                    &self.rotated_stem,
+                    &self.rotated_log_file_names,
+                    &pending_log_file,
+                )?;
+
+                self.shift_sequential_log_file_names();
+
+                self.rotated_log_file_names.push(format!("{}.1", self.rotated_stem));
+            }
+        }
+
+        for rotated_log_file_name in self.rotated_log_file_names.clone() {
+            let rotated_log_file = Path::join(&self.folder_path, &rotated_log_file_name);
+
+            if !rotated_log_file.is_file() {
+                continue;
+            }
+
+            let mut partially_compressed = false;
+
+            for rotated_log_file_compressed in
+                compressed_paths(&self.folder_path, &rotated_log_file_name)
+            {
+                if rotated_log_file_compressed.is_file() {
+                    fs::remove_file(&rotated_log_file_compressed)?;
+
+                    partially_compressed = true;
+                }
+            }
+
+            if partially_compressed && self.compress {
+                // the metadata of the interrupted rotation is not persisted
+                self.process_rotated_log_file(&rotated_log_file_name, BTreeMap::new());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Merge the runs of consecutive rotated log files smaller than `compaction_size` into the newest file of each run. The merged file replaces the newest file before the older files are removed, so a crash in between leaves duplicated lines rather than lost ones.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    fn compact_rotated_log_files(&mut self, compaction_size: u64) -> io::Result<()> {
+        self.compressions.retain(|(_, job)| !self.worker.is_finished(*job));
+
+        // (the names, the paths, the extension if they are compressed)
+        type Run = (Vec<String>, Vec<PathBuf>, Option<&'static str>);
+
+        let mut runs: Vec<Run> = Vec::new();
+        let mut run: Run = (Vec::new(), Vec::new(), None);
+
+        for rotated_log_file_name in self.rotated_log_file_names.iter() {
+            let rotated_log_file = Path::join(&self.folder_path, rotated_log_file_name);
+
+            let mut forms: Vec<(PathBuf, Option<&'static str>)> = COMPRESSED_EXTENSIONS
+                .iter()
+                .map(|extension| {
+                    let path = Path::join(
+                        &self.folder_path,
+                        format!("{}{}", rotated_log_file_name, extension),
+                    );
+
+                    (path, Some(*extension))
+                })
+                .filter(|(path, _)| path.is_file())
+                .collect();
+
+            if rotated_log_file.is_file() {
+                forms.push((rotated_log_file, None));
+            }
+
+            // a file with more than one of the forms is being compressed
+            let candidate =
+                if forms.len() == 1 { forms.pop() } else { None }.filter(|(path, _)| {
+                    !self.compressions.iter().any(|(pending_path, _)| pending_path == path)
+                        && !self.is_exempt_from_retention(rotated_log_file_name)
+                        && fs::metadata(path).map(|m| m.len() < compaction_size).unwrap_or(false)
+                });
+
+            match candidate {
+                Some((path, compressed)) => {
+                    if !run.0.is_empty() && run.2 != compressed {
+                        runs.push(std::mem::take(&mut run));
+                    }
+
+                    run.0.push(rotated_log_file_name.clone());
+                    run.1.push(path);
+                    run.2 = compressed;
+                },
+                None => runs.push(std::mem::take(&mut run)),
+            }
+        }
+
+        runs.push(run);
+
+        for (names, paths, _) in runs.into_iter().filter(|(names, ..)| names.len() > 1) {
+            let newest = &paths[paths.len() - 1];
+
+            let file_name = newest.file_name().unwrap().to_string_lossy();
+
+            let compacting = Path::join(&self.folder_path, format!(".{}.compacting", file_name));
+
+            let merge = || -> io::Result<()> {
+                let mut file_w = File::create(&compacting)?;
+
+                for path in paths.iter() {
+                    io::copy(&mut File::open(path)?, &mut file_w)?;
+                }
+
+                file_w.sync_all()?;
+
+                copy_file_attributes(&File::open(newest)?, &file_w)?;
+
+                fs::rename(&compacting, newest)
+            };
+
+            if let Err(err) = merge() {
+                if fs::remove_file(&compacting).is_err() {
+                    // do nothing
+                }
+
+                return Err(err);
+            }
+
+            for path in &paths[..paths.len() - 1] {
+                fs::remove_file(path)?;
+            }
+
+            let merged = &names[..names.len() - 1];
+
+            self.rotated_log_file_names.retain(|name| !merged.contains(name));
+        }
+
+        Ok(())
+    }
+
+    /// Run the processor chain on a rotated log file in a new thread, or on the current thread in the deterministic mode. The compression is the first processor if it is enabled.
+    fn process_rotated_log_file(
+        &mut self,
+        rotated_log_file_name: &str,
+        meta: BTreeMap<String, String>,
+    ) {
+        let rotated_log_file = Path::join(&self.folder_path, rotated_log_file_name);
+
+        let mut processors: Vec<Arc<dyn RotationProcessor>> =
+            Vec::with_capacity(self.processors.len() + 1);
+
+        let pending_path = if self.compress {
+            processors.push(Arc::new(Compression {
+                method:  self.compress_method,
+                threads: self.compression_threads,
+                tail:    self.compression_tail,
+                level:   self.compression_level,
+                verify:  self.compression_verify,
+            }));
+
+            Path::join(
+                &self.folder_path,
+                format!("{}{}", rotated_log_file_name, self.compress_method.extension()),
+            )
+        } else {
+            rotated_log_file.clone()
+        };
+
+        processors.extend(self.processors.iter().cloned());
+
+        let tee = self.tee.clone();
+
+        let print_err = move |s| match &tee {
+            Some(tee) => match tee {
+                Tee::Stdout => {
+                    eprintln!("{}", s);
+                },
+                Tee::Stderr => {
+                    println!("{}", s);
+                },
+            },
+            None => {
+                eprintln!("{}", s);
+            },
+        };
+
+        self.compressions.retain(|(_, job)| !self.worker.is_finished(*job));
+
+        let processor_retries = self.processor_retries;
+        let retry_delay = self.retry_delay;
+        let dead_letter_dir = self.dead_letter_dir.clone();
+        let dead_letter_callback = self.dead_letter_callback.clone();
+
+        // the spans of the processing are children of the one of the rotation, though they are on another thread
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!("process_rotated_log_file", path = ?rotated_log_file);
+
+        let process = move || {
+            #[cfg(feature = "tracing")]
+            let _enter = span.enter();
+
+            let mut path = rotated_log_file;
+
+            for processor in processors {
+                #[cfg(feature = "tracing")]
+                let _span =
+                    tracing::debug_span!("process", processor = ?processor, path = ?path).entered();
+
+                let mut result = processor.process_with_meta(&path, &meta);
+
+                let mut delay = retry_delay;
+
+                for _ in 0..processor_retries {
+                    if result.is_ok() || !path.exists() {
+                        break;
+                    }
+
+                    thread::sleep(delay);
+
+                    delay = delay.saturating_mul(2);
+
+                    result = processor.process_with_meta(&path, &meta);
+                }
+
+                match result {
+                    Ok(p) => path = p,
+                    Err(err) => {
+                        print_err(err.to_string());
+
+                        if let Some(dead_letter_dir) = dead_letter_dir.as_ref() {
+                            if path.exists() {
+                                let processor = format!("{:?}", processor);
+
+                                match dead_letter::park(dead_letter_dir, &path, &processor, &err) {
+                                    Ok(parked) => {
+                                        if let Some(callback) = dead_letter_callback.as_ref() {
+                                            callback(DeadLetter {
+                                                path: parked,
+                                                processor,
+                                                error: err,
+                                            });
+                                        }
+                                    },
+                                    Err(err) => print_err(err.to_string()),
+                                }
+                            }
+                        }
+
+                        break;
+                    },
+                }
+
+                // the rotated log file is deleted because of the count limit
+                if !path.exists() {
+                    break;
+                }
+            }
+        };
+
+        if self.deterministic {
+            process();
+        } else {
+            let job = self.worker.submit(Box::new(process));
+
+            self.compressions.push((pending_path, job));
+        }
+    }
+
+    /// Call `callback` when a rotated log file is parked in the dead-letter directory. It is called on the background thread which processes the rotated log file.
+    pub fn set_dead_letter_callback(&mut self, callback: Box<dyn Fn(DeadLetter) + Send + Sync>) {
+        self.dead_letter_callback = Some(Arc::new(callback));
+    }
+
+    /// Call `callback` with the error when a write to the extra sink set by `PipeLoggerBuilder::set_extra_sink` fails. It is called on the thread which writes to the logger.
+    pub fn set_sink_error_callback(&mut self, callback: Box<dyn Fn(&io::Error) + Send>) {
+        self.sink_error_callback = Some(callback);
+    }
+
+    /// Attach metadata (e.g. a build ID, or the range of the requests covered) to the next rotation. It is passed to the processors of the rotated log file through `RotationProcessor::process_with_meta`, and replaces the metadata set before. It is not persisted, so it is lost if the logger is dropped before the rotation.
+    pub fn set_next_rotation_meta(&mut self, meta: BTreeMap<String, String>) {
+        self.next_rotation_meta = meta;
+    }
+
+    /// Exempt a rotated log file from the `count` retention, such as a file which has to be kept after an incident. The path can be the one with or without the extension of the compression. Returns `false` if it is not a rotated log file of this logger. The exemption is not persisted.
+    pub fn pin<P: AsRef<Path>>(&mut self, rotated_log_file: P) -> bool {
+        match self.rotated_log_file_name(rotated_log_file.as_ref()) {
+            Some(rotated_log_file_name) => {
+                if !self.pinned_log_file_names.contains(&rotated_log_file_name) {
+                    self.pinned_log_file_names.push(rotated_log_file_name);
+                }
+
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Remove the exemption made by `pin`. Returns `false` if the rotated log file was not pinned.
+    pub fn unpin<P: AsRef<Path>>(&mut self, rotated_log_file: P) -> bool {
+        match self.rotated_log_file_name(rotated_log_file.as_ref()) {
+            Some(rotated_log_file_name) => {
+                let len = self.pinned_log_file_names.len();
+
+                self.pinned_log_file_names.retain(|name| *name != rotated_log_file_name);
+
+                self.pinned_log_file_names.len() != len
+            },
+            None => false,
+        }
+    }
+
+    /// Get the name of a tracked rotated log file by its path, with or without the extension of the compression.
+    fn rotated_log_file_name(&self, rotated_log_file: &Path) -> Option<String> {
+        let file_name = rotated_log_file.file_name()?.to_str()?;
+
+        let file_name = strip_compressed_extension(file_name).map_or(file_name, |(name, _)| name);
+
+        self.rotated_log_file_names.iter().find(|name| *name == file_name).cloned()
+    }
+
+    /// Whether a rotated log file is exempt from the `count` retention.
+    fn is_exempt_from_retention(&self, rotated_log_file_name: &str) -> bool {
+        self.pinned_log_file_names.iter().any(|name| name == rotated_log_file_name)
+            || self
+                .retention_exemption
+                .as_ref()
+                .map(|regex| regex.is_match(rotated_log_file_name))
+                .unwrap_or(false)
+    }
+
+    /// The size of the active log file in bytes, as tracked by this logger.
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
+
+    /// The number of failed writes to the tee output.
+    pub fn tee_error_count(&self) -> u64 {
+        match &self.tee_buffer {
+            Some(tee_buffer) => self.tee_error_count + tee_buffer.error_count(),
+            None => self.tee_error_count,
+        }
+    }
+
+    /// The rolling rates of the lines and bytes written to this logger.
+    pub fn rates(&self) -> LogRates {
+        self.rate_counter.rates()
+    }
+
+    /// Call `callback` when the write rate of this logger stays above `threshold`. It is called once until the rate drops below the threshold again, on the thread which writes to this logger. The rate is checked when something is written after each second.
+    pub fn set_volume_alert(
+        &mut self,
+        threshold: VolumeThreshold,
+        callback: Box<dyn Fn(VolumeAlert) + Send>,
+    ) {
+        self.volume_monitor = Some(VolumeMonitor::new(threshold, callback));
+    }
+
+    /// Call `callback` when the log file reopened after a rotation fails the verification of `PipeLoggerBuilder::set_verify_reopen`. It is called on the thread which writes to this logger.
+    pub fn set_reopen_mismatch_callback(&mut self, callback: Box<dyn Fn(ReopenMismatch) + Send>) {
+        self.reopen_callback = Some(callback);
+    }
+
+    fn record_rate(&mut self, lines: u64, bytes: u64) {
+        self.line_count += lines;
+
+        if let Some(completed_second) = self.rate_counter.record(lines, bytes) {
+            if let Some(volume_monitor) = self.volume_monitor.as_mut() {
+                volume_monitor.check(&self.rate_counter, completed_second);
+            }
+        }
+    }
+
+    /// The interval of the heartbeat lines.
+    pub fn heartbeat_interval(&self) -> Option<Duration> {
+        self.heartbeat_interval
+    }
+
+    /// Write a heartbeat line with the numbers of lines written and rotations done by this logger, such as `alive, 1234 lines, 5 rotations`. The heartbeat line itself is not counted, nor written to the tee output. If the log is rotated, this method returns the renamed path.
+    pub fn write_heartbeat(&mut self) -> io::Result<Option<PathBuf>> {
+        let line = format!("alive, {} lines, {} rotations\n", self.line_count, self.rotation_count);
+
+        self.write_bytes(line.as_bytes())
+    }
+
+    /// The number of writes to the tee output dropped because the tee buffer was full.
+    pub fn tee_dropped_count(&self) -> u64 {
+        self.tee_dropped_count
+    }
+
+    /// The number of failed writes and flushes to the extra sink set by `PipeLoggerBuilder::set_extra_sink`.
+    pub fn extra_sink_error_count(&self) -> u64 {
+        self.extra_sink_error_count
+    }
+
+    /// Write an HTTP access record in the Apache combined log format, with a new line. If the log is rotated, this method returns the renamed path.
+    #[inline]
+    pub fn write_access(&mut self, record: &AccessRecord) -> io::Result<Option<PathBuf>> {
+        self.write_line(record.to_string())
+    }
+
+    /// Write a security event in the CEF or LEEF format, with a new line. If the log is rotated, this method returns the renamed path.
+    #[inline]
+    pub fn write_security_event(
+        &mut self,
+        header: &SecurityEventHeader,
+        format: SecurityEventFormat,
+        event: &SecurityEvent,
+    ) -> io::Result<Option<PathBuf>> {
+        self.write_line(header.format(format, event))
+    }
+
+    /// Write an entry of the W3C extended log format, with a new line. The values need to correspond to the fields set by `PipeLoggerBuilder::set_w3c_fields`. If the log is rotated, this method returns the renamed path.
+    pub fn write_w3c<S: AsRef<str>>(&mut self, values: &[S]) -> io::Result<Option<PathBuf>> {
+        match &self.w3c_fields {
+            Some(w3c_fields) if w3c_fields.len() == values.len() => (),
+            Some(w3c_fields) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "{} values are needed, but {} are given.",
+                        w3c_fields.len(),
+                        values.len()
+                    ),
+                ));
+            },
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "The fields of the W3C extended log format are not set.",
+                ));
+            },
+        }
+
+        self.write_line(format_w3c_entry(values))
+    }
+
+    /// Write some bytes to the extra sink and the tee output, after they are written to the log file (even if that fails). The stdout/stderr handle is locked once for all of the parts.
+    fn print(&mut self, parts: &[&[u8]]) {
+        if let Some(extra_sink) = &self.extra_sink {
+            if let Err(err) = extra_sink.write_all_parts(parts) {
+                self.sink_error(&err);
+            }
+        }
+
+        if let Some(tee_buffer) = &self.tee_buffer {
+            if !tee_buffer.send(&parts.concat()) {
+                self.tee_dropped_count += 1;
+            }
+
+            return;
+        }
+
+        if let Some(tee) = &self.tee {
+            // A broken tee output (e.g. a closed pipe) must not affect writing the log file
+            let result = match tee {
+                Tee::Stdout => write_all_parts(&mut io::stdout().lock(), parts),
+                Tee::Stderr => write_all_parts(&mut io::stderr().lock(), parts),
+            };
+
+            if result.is_err() {
+                self.tee_error_count += 1;
+            }
+        }
+    }
+
+    #[inline]
+    fn sink_error(&mut self, err: &io::Error) {
+        self.extra_sink_error_count += 1;
+
+        if let Some(callback) = self.sink_error_callback.as_ref() {
+            callback(err);
+        }
+    }
+}
+
+#[inline]
+fn count_lines(buf: &[u8]) -> u64 {
+    buf.iter().filter(|b| **b == b'\n').count() as u64
+}
+
+#[inline]
+fn write_all_parts<W: Write>(writer: &mut W, parts: &[&[u8]]) -> io::Result<()> {
+    parts.iter().try_for_each(|part| writer.write_all(part))
+}
+
+/// Use the extended-length form (`\\?\`) of a long absolute path, so that the path and its rotated paths can exceed `MAX_PATH`.
+#[cfg(windows)]
+fn to_extended_length_path(path: PathBuf) -> PathBuf {
+    use std::path::{Component, Prefix};
+
+    const MAX_PATH: usize = 260;
+    // -%Y-%m-%d-%H-%M-%S + $.3f + .zst
+    const ROTATED_FILE_NAME_EXTRA_LENGTH: usize = 28;
+
+    if path.as_os_str().len() + ROTATED_FILE_NAME_EXTRA_LENGTH < MAX_PATH {
+        return path;
+    }
+
+    let mut components = path.components();
+
+    let mut extended_length_path = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(_) => {
+                let mut s = std::ffi::OsString::from(r"\\?\");
+                s.push(prefix.as_os_str());
+                s
+            },
+            Prefix::UNC(server, share) => {
+                let mut s = std::ffi::OsString::from(r"\\?\UNC\");
+                s.push(server);
+                s.push(r"\");
+                s.push(share);
+                s
+            },
+            // already verbatim, or a device path
+            _ => return path,
+        },
+        _ => return path,
+    };
+
+    for component in components {
+        match component {
+            Component::RootDir => (),
+            Component::Normal(name) => {
+                extended_length_path.push(r"\");
+                extended_length_path.push(name);
+            },
+            // verbatim paths are not normalized by Windows
+            _ => return path,
+        }
+    }
+
+    PathBuf::from(extended_length_path)
+}
+
+/// Split an absolutized log path into the path itself, its directory, its file name and the index of the extension in the file name, by `name_split`. The file name is the one from which the names of the rotated log files are derived, which is shortened if it is too long.
+fn split_log_path(
+    log_path: &Path,
+    name_split: &NameSplit,
+) -> io::Result<(PathBuf, PathBuf, String, usize)> {
+    let file_path = log_path.absolutize()?.into_owned();
+
+    let folder_path = match file_path.parent() {
+        Some(folder_path) => folder_path.to_path_buf(),
+        None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a file path")),
+    };
+
+    let file_name = match file_path.file_name() {
+        Some(file_name) => file_name.to_string_lossy().into_owned(),
+        None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a file path")),
+    };
+
+    let (file_name, file_name_point_index) =
+        rotated_base_name(&file_name, name_split.split_index(&file_name)?);
+
+    Ok((file_path, folder_path, file_name, file_name_point_index))
+}
+
+/// The formats of the timestamps in the names of rotated log files which have ever been produced, from the newest one.
+pub(crate) const ROTATED_LOG_FILE_TIMESTAMP_PATTERNS: [&str; 5] = [
+    "^-[1-2][0-9]{3}(-[0-5][0-9]){5}-[0-9]{3}$", // -%Y-%m-%d-%H-%M-%S + $.3f
+    "^-[1-2][0-9]{3}(-[0-5][0-9]){5}-[0-9]{6}$", // -%Y-%m-%d-%H-%M-%S + $.6f
+    "^-[1-2][0-9]{3}(-[0-5][0-9]){5}-[0-9]{3}-[0-9]{3,}$", // -%Y-%m-%d-%H-%M-%S + $.3f + -sequence
+    "^-[1-2][0-9]{3}(-[0-5][0-9]){5}$",          // -%Y-%m-%d-%H-%M-%S
+    "^-[1-2][0-9]{3}(-[0-5][0-9]){5}-[0-9]{6}-[0-9]{3,}$", // -%Y-%m-%d-%H-%M-%S + $.6f + -sequence
+];
+
+/// Find the rotated log files of a log file in its directory, named in a `RotationNaming` (in any of `ROTATED_LOG_FILE_TIMESTAMP_PATTERNS` for `RotationNaming::Timestamp`). The names of compressed files (in any `CompressionMethod`) are returned without the extension of the compression. The names are sorted from the oldest to the newest.
+fn scan_rotated_log_file_names(
+    folder_path: &Path,
+    file_name: &str,
+    file_name_point_index: usize,
+    rotation_naming: &RotationNaming,
+) -> io::Result<Vec<String>> {
+    let mut rotated_log_file_names = Vec::new();
+
+    let re = rotation_naming
+        .pattern()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+        .and_then(|pattern| {
+            Regex::new(&pattern).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+        })?;
+
+    let (stem, ext) = rotation_naming.split(file_name, file_name_point_index);
+
+    let varying_part = |name: &str| -> Option<String> {
+        let varying = name.strip_prefix(stem)?.strip_suffix(ext)?;
+
+        if re.is_match(varying) {
+            Some(varying.to_string())
+        } else {
+            None
+        }
+    };
+
+    for entry in folder_path.read_dir()?.filter_map(|entry| entry.ok()) {
+        let rotated_log_file_path = entry.path();
+
+        if !rotated_log_file_path.is_file() {
+            continue;
+        }
+
+        let rotated_log_file_name =
+            match rotated_log_file_path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => name,
+                None => continue,
+            };
+
+        if let Some(varying) = varying_part(rotated_log_file_name) {
+            rotated_log_file_names.push((rotated_log_file_name.to_string(), varying));
+        } else if let Some((name, _)) = strip_compressed_extension(rotated_log_file_name) {
+            if let Some(varying) = varying_part(name) {
+                rotated_log_file_names.push((name.to_string(), varying));
+            }
+        }
+    }
+
+    rotation_naming.sort(&mut rotated_log_file_names);
+
+    Ok(rotated_log_file_names.into_iter().map(|(name, _)| name).collect())
+}
+
+/// Remove rotated log files in both of their forms. The directory is read once to find out which of the forms exist, instead of trying to remove both of them for every file.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(count = rotated_log_file_names.len()))
+)]
+fn remove_rotated_log_files(folder_path: &Path, rotated_log_file_names: &[String]) {
+    let rotated_log_file_names: HashSet<&str> =
+        rotated_log_file_names.iter().map(String::as_str).collect();
+
+    let entries = match folder_path.read_dir() {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let file_name = entry.file_name();
+
+        let file_name = match file_name.to_str() {
+            Some(file_name) => file_name,
+            None => continue,
+        };
+
+        if rotated_log_file_names
+            .contains(strip_compressed_extension(file_name).map_or(file_name, |(name, _)| name))
+            && fs::remove_file(entry.path()).is_err()
+        {
+            // do nothing
+        }
+    }
+}
+
+/// Parse the timestamp in the name of a rotated log file, in any of `ROTATED_LOG_FILE_TIMESTAMP_PATTERNS`.
+fn parse_rotated_log_file_timestamp(
+    rotated_log_file_name: &str,
+    file_name_point_index: usize,
+) -> Option<DateTime<Utc>> {
+    let start = file_name_point_index + 1;
+
+    // %Y-%m-%d-%H-%M-%S, optionally followed by - and 3 or 6 digits of fractional seconds
+    let datetime = rotated_log_file_name.get(start..start + 19)?;
+    let fraction: &str = rotated_log_file_name.get(start + 19..)?;
+    let fraction = fraction.strip_prefix('-').unwrap_or("");
+    let fraction =
+        &fraction[..fraction.find(|c: char| !c.is_ascii_digit()).unwrap_or(fraction.len())];
+
+    let datetime = NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d-%H-%M-%S").ok()?;
+    let nanoseconds = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<i64>().ok()? * 10i64.pow(9 - fraction.len().min(9) as u32)
+    };
+
+    Some(Utc.from_utc_datetime(&datetime) + chrono::Duration::nanoseconds(nanoseconds))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How `open_log_file` opens the log file.
+enum OpenMode {
+    /// Open or create the log file for appending.
+    Append,
+    /// Create the log file for appending, failing if it exists.
+    CreateNew,
+    /// Open the existing log file for writing, and truncate it.
+    Truncate,
+}
+
+/// Open a log file for appending, or truncate it, in an `OpenMode`. If `lock` is `true`, an exclusive lock is taken, and an error of `io::ErrorKind::WouldBlock` is returned if the file has been locked. If `follow_symlinks` is `false`, opening a symbolic link fails on Unix-like systems.
+fn open_log_file(
+    path: &Path,
+    mode: OpenMode,
+    lock: bool,
+    follow_symlinks: bool,
+) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+
+    match mode {
+        OpenMode::Append => {
+            options.create(true).append(true);
+        },
+        OpenMode::CreateNew => {
+            options.create_new(true).append(true);
+        },
+        OpenMode::Truncate => {
+            options.write(true).truncate(true);
+        },
+    }
+
+    #[cfg(unix)]
+    if !follow_symlinks {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        options.custom_flags(libc::O_NOFOLLOW);
+    }
+
+    #[cfg(not(unix))]
+    let _ = follow_symlinks;
+
+    #[cfg(windows)]
+    if lock {
+        use std::os::windows::fs::OpenOptionsExt;
+
+        const FILE_SHARE_READ: u32 = 0x00000001;
+
+        options.share_mode(FILE_SHARE_READ);
+    }
+
+    let file = match options.open(path) {
+        Ok(file) => file,
+        #[cfg(windows)]
+        Err(ref err) if lock && err.raw_os_error() == Some(32) => {
+            // ERROR_SHARING_VIOLATION
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                format!("`{}` is locked.", path.to_string_lossy()),
+            ));
+        },
+        Err(err) => return Err(err),
+    };
+
+    #[cfg(unix)]
+    if lock {
+        use std::os::unix::io::AsRawFd;
+
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let err = io::Error::last_os_error();
+
+            return Err(if err.kind() == io::ErrorKind::WouldBlock {
+                io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    format!("`{}` is locked.", path.to_string_lossy()),
+                )
+            } else {
+                err
+            });
+        }
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    let _ = lock;
+
+    Ok(file)
+}
+
+/// Make a newly created file have the same permissions and, on Unix-like systems, the same owner as an existing file if possible. With the `xattr` feature on Linux, extended attributes (including the SELinux security context) are also copied if possible.
+fn copy_file_attributes(from: &File, to: &File) -> io::Result<()> {
+    let metadata = from.metadata()?;
+
+    to.set_permissions(metadata.permissions())?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::{fs::MetadataExt, io::AsRawFd};
+
+        // changing the owner needs privileges, so it is just attempted
+        unsafe {
+            libc::fchown(to.as_raw_fd(), metadata.uid(), metadata.gid());
+        }
+    }
+
+    #[cfg(all(feature = "xattr", target_os = "linux"))]
+    copy_xattrs(from, to);
+
+    Ok(())
+}
+
+/// Copy extended attributes from a file to another file. Attributes which cannot be read or written (e.g. not supported by the file system, or not permitted) are skipped.
+#[cfg(all(feature = "xattr", target_os = "linux"))]
+fn copy_xattrs(from: &File, to: &File) {
+    use std::{ffi::CString, os::unix::io::AsRawFd, ptr};
+
+    let from_fd = from.as_raw_fd();
+    let to_fd = to.as_raw_fd();
+
+    let size = unsafe { libc::flistxattr(from_fd, ptr::null_mut(), 0) };
+
+    if size <= 0 {
+        return;
+    }
+
+    let mut names = vec![0u8; size as usize];
+
+    let size = unsafe {
+        libc::flistxattr(from_fd, names.as_mut_ptr() as *mut std::os::raw::c_char, names.len())
+    };
+
+    if size <= 0 {
+        return;
+    }
+
+    names.truncate(size as usize);
+
+    for name in names.split(|b| *b == 0).filter(|name| !name.is_empty()) {
+        let name = match CString::new(name) {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+
+        let size = unsafe { libc::fgetxattr(from_fd, name.as_ptr(), ptr::null_mut(), 0) };
+
+        if size < 0 {
+            continue;
+        }
+
+        let mut value = vec![0u8; size as usize];
+
+        let size = unsafe {
+            libc::fgetxattr(
+                from_fd,
+                name.as_ptr(),
+                value.as_mut_ptr() as *mut std::ffi::c_void,
+                value.len(),
+            )
+        };
+
+        if size < 0 {
+            continue;
+        }
+
+        unsafe {
+            libc::fsetxattr(
+                to_fd,
+                name.as_ptr(),
+                value.as_ptr() as *const std::ffi::c_void,
+                size as usize,
+                0,
+            );
+        }
+    }
+}
+
+/// The name of a rotated log file named in `RotationNaming::Sequential` at the next index.
+fn next_sequential_log_file_name(stem: &str, rotated_log_file_name: &str) -> Option<String> {
+    let index = rotated_log_file_name.strip_prefix(stem)?.strip_prefix('.')?;
+
+    Some(format!("{}.{}", stem, index.parse::<u64>().ok()? + 1))
+}
+
+/// Rename the rotated log files (in all of their forms) named in `RotationNaming::Sequential` to the next indices, from the oldest one, and then rename the pending rotated log file to the index `1`.
+fn shift_sequential_log_files(
+    folder_path: &Path,
+    stem: &str,
+    rotated_log_file_names: &[String],
+    pending_log_file: &Path,
+) -> io::Result<()> {
+    for rotated_log_file_name in rotated_log_file_names {
+        let next_rotated_log_file_name =
+            match next_sequential_log_file_name(stem, rotated_log_file_name) {
+                Some(name) => name,
+                None => continue,
+            };
+
+        let from = [Path::join(folder_path, rotated_log_file_name)]
+            .into_iter()
+            .chain(compressed_paths(folder_path, rotated_log_file_name));
+        let to = [Path::join(folder_path, &next_rotated_log_file_name)]
+            .into_iter()
+            .chain(compressed_paths(folder_path, &next_rotated_log_file_name));
+
+        for (from, to) in from.zip(to) {
+            if from.exists() {
+                fs::rename(from, to)?;
+            }
+        }
+    }
+
+    fs::rename(pending_log_file, Path::join(folder_path, format!("{}.1", stem)))
+}
+
+/// Hard-link a file, or copy it if hard-linking is not possible (e.g. across file systems).
+fn link_or_copy(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::hard_link(from, to) {
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Err(err),
+        Err(_) => fs::copy(from, to).map(|_| ()),
+    }
+}
+
+/// Rename a file, or copy it if renaming is not possible across file systems. Returns `true` if it is renamed. A symbolic link is copied from its target, because renaming it would move the link rather than the file. A copy is recorded in `journal`, which is left for the caller to remove after truncating the source file.
+fn rename_or_copy(from: &Path, to: &Path, journal: &Path) -> io::Result<bool> {
+    if fs::symlink_metadata(from)?.file_type().is_symlink() {
+        copy_with_journal(from, to, journal)?;
+
+        return Ok(false);
+    }
+
+    match fs::rename(from, to) {
+        Ok(_) => Ok(true),
+        Err(err) if is_cross_device_error(&err) => {
+            copy_with_journal(from, to, journal)?;
+
+            Ok(false)
+        },
+        Err(err) => Err(err),
+    }
+}
+
+/// Copy a file to a hidden temporary file and then rename it, after recording the file name of the destination in `journal`. Either the destination is complete or it does not exist.
+fn copy_with_journal(from: &Path, to: &Path, journal: &Path) -> io::Result<()> {
+    let mut file_journal = File::create(journal)?;
+
+    file_journal.write_all(to.file_name().unwrap().to_string_lossy().as_bytes())?;
+    file_journal.sync_all()?;
+
+    let copying = copying_path(to);
+
+    fs::copy(from, &copying)?;
+
+    File::open(&copying)?.sync_all()?;
+
+    fs::rename(copying, to)
+}
+
+/// The path of the hidden temporary file to which a file is copied.
+#[inline]
+fn copying_path(path: &Path) -> PathBuf {
+    path.with_file_name(format!(".{}.copying", path.file_name().unwrap().to_string_lossy()))
+}
+
+#[inline]
+fn is_cross_device_error(err: &io::Error) -> bool {
+    #[cfg(unix)]
+    {
+        err.raw_os_error() == Some(libc::EXDEV)
+    }
+
+    #[cfg(windows)]
+    {
+        // ERROR_NOT_SAME_DEVICE
+        err.raw_os_error() == Some(17)
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = err;
+
+        false
+    }
+}
+
+/// Create an xz encoder. It uses the multi-threaded encoder of liblzma if more than one thread is available.
+fn create_compressor<W: Write>(
+    writer: W,
+    compression_threads: usize,
+    compression_level: u32,
+) -> XzEncoder<W> {
+    let threads = match compression_threads {
+        0 => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        n => n,
+    };
+
+    if threads > 1 {
+        let stream = MtStreamBuilder::new()
+            .threads(threads.min(u32::MAX as usize) as u32)
+            .preset(compression_level)
+            .check(Check::Crc64)
+            .encoder();
+
+        if let Ok(stream) = stream {
+            return XzEncoder::new_stream(writer, stream);
+        }
+    }
+
+    XzEncoder::new(writer, compression_level)
+}
+
+/// Compress a rotated log file through `method` and then remove the original one. If `compression_tail` is set and the rotated log file is bigger than it, only the last `compression_tail` bytes are stored, after a truncation marker. If `verify` is `true`, the original one is removed only if the compressed one is decoded to the same contents.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(rotated_log_file = ?rotated_log_file, size = tracing::field::Empty)
+    )
+)]
+fn compress_log_file(
+    rotated_log_file: &Path,
+    rotated_log_file_compressed: &Path,
+    method: CompressionMethod,
+    compression_threads: usize,
+    compression_tail: Option<u64>,
+    compression_level: CompressionLevel,
+    verify: bool,
+) -> io::Result<()> {
+    // an invalid level would make the encoder panic
+    if let Some(level) = compression_level.find_out_of_range(method) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("The compression level {} is out of the range of {:?}.", level, method),
+        ));
+    }
+
+    let file_w = File::create(rotated_log_file_compressed)?;
+
+    let mut file_r = match File::open(rotated_log_file) {
+        Ok(file_r) => file_r,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+            // The rotated log file is deleted because of the count limit
+            drop(file_w);
+            if fs::remove_file(rotated_log_file_compressed).is_err() {
+                // do nothing
+            }
+            return Ok(());
+        },
+        Err(err) => return Err(err),
+    };
+
+    copy_file_attributes(&file_r, &file_w)?;
+
+    let size = file_r.metadata()?.len();
+
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("size", size);
+
+    let compression_level = compression_level.resolve(&file_w, size);
+
+    let mut compressor = method.encoder(file_w, compression_threads, compression_level)?;
+
+    let mut marker = String::new();
+    let mut start = 0;
+
+    if let Some(compression_tail) = compression_tail {
+        let file_size = file_r.metadata()?.len();
+
+        if file_size > compression_tail {
+            let truncated_size = file_size - compression_tail;
+
+            file_r.seek(SeekFrom::Start(truncated_size))?;
+
+            marker = format!("[pipe-logger: {} bytes truncated]\n", truncated_size);
+            start = truncated_size;
+
+            compressor.write_all(marker.as_bytes())?;
+        }
+    }
+
+    let mut buffer = [0u8; BUFFER_SIZE];
+
+    loop {
+        match file_r.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(c) => compressor.write_all(&buffer[..c])?,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                // The rotated log file is deleted because of the count limit
+                drop(compressor);
+                if fs::remove_file(rotated_log_file_compressed).is_err() {
+                    // do nothing
+                }
+                return Ok(());
+            },
+            Err(err) => return Err(err),
+        }
+    }
+
+    let file_w = compressor.finish()?;
+
+    if verify {
+        file_w.sync_all()?;
+
+        file_r.seek(SeekFrom::Start(start))?;
+
+        let decoder = method.decoder(File::open(rotated_log_file_compressed)?)?;
+
+        if !is_same_contents(marker.as_bytes().chain(&mut file_r), decoder)? {
+            drop(file_w);
+
+            fs::remove_file(rotated_log_file_compressed)?;
+
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{:?} is corrupted.", rotated_log_file_compressed),
+            ));
+        }
+    }
+
+    drop(file_r);
+
+    if fs::remove_file(rotated_log_file).is_err() {
+        // do nothing
+    }
+
+    Ok(())
+}
+
+/// Check whether two readers have the same contents. A read error of `b`, such as a corrupted xz stream, is treated as a difference.
+fn is_same_contents<A: Read, B: Read>(mut a: A, mut b: B) -> io::Result<bool> {
+    let mut buffer_a = [0u8; BUFFER_SIZE];
+    let mut buffer_b = [0u8; BUFFER_SIZE];
+
+    loop {
+        let c = a.read(&mut buffer_a)?;
+
+        if c == 0 {
+            return Ok(matches!(b.read(&mut buffer_b), Ok(0)));
+        }
+
+        if b.read_exact(&mut buffer_b[..c]).is_err() || buffer_a[..c] != buffer_b[..c] {
+            return Ok(false);
         }
     }
 }
@@ -0,0 +1,152 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use chrono::SecondsFormat;
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::{global, PipeLogger, PipeLoggerBuilder, PipeLoggerBuilderError};
+
+/// The record format used by `PipeLoggerBackend` by default.
+pub const DEFAULT_RECORD_FORMAT: &str = "{timestamp} [{level}] {target}: {message}";
+
+/// A backend of the `log` crate, enabled by the `log` feature. Every record is written as a line through a `PipeLogger`, so the log file is rotated and compressed as usual. Once it is installed, its logger is also the process-global logger returned by `global`.
+///
+/// The record format can contain `{timestamp}` (RFC 3339 in milliseconds, from the clock of the logger), `{level}`, `{target}` and `{message}`.
+pub struct PipeLoggerBackend {
+    logger: Arc<Mutex<PipeLogger>>,
+    level:  LevelFilter,
+    format: String,
+}
+
+impl PipeLoggerBackend {
+    /// Create a backend which writes the records at `level` or above in the `DEFAULT_RECORD_FORMAT`.
+    #[inline]
+    pub fn new(logger: PipeLogger, level: LevelFilter) -> PipeLoggerBackend {
+        PipeLoggerBackend::from_shared(Arc::new(Mutex::new(logger)), level)
+    }
+
+    /// Create a backend which writes through a logger shared with the process-global logger.
+    #[inline]
+    pub(crate) fn from_shared(
+        logger: Arc<Mutex<PipeLogger>>,
+        level: LevelFilter,
+    ) -> PipeLoggerBackend {
+        PipeLoggerBackend {
+            logger,
+            level,
+            format: DEFAULT_RECORD_FORMAT.to_string(),
+        }
+    }
+
+    /// Set the record format.
+    #[inline]
+    pub fn with_format<S: Into<String>>(mut self, format: S) -> PipeLoggerBackend {
+        self.format = format.into();
+
+        self
+    }
+
+    #[inline]
+    pub fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    #[inline]
+    pub fn format(&self) -> &str {
+        &self.format
+    }
+
+    /// Install its logger as the process-global logger, install this backend as the logger of the `log` crate, and set the maximum level of the `log` crate to the level of this backend, in the same order as the `init` function. It can only be installed once, and not after the `init` function. If another logger of the `log` crate has been installed, the global logger stays installed, and `PipeLoggerBuilderError::LogLoggerAlreadyInitialized` is returned.
+    pub fn init(self) -> Result<(), PipeLoggerBuilderError> {
+        if global::is_initialized() {
+            return Err(PipeLoggerBuilderError::GlobalLoggerAlreadyInitialized);
+        }
+
+        global::install(self.logger.clone())?;
+
+        self.register()
+    }
+
+    /// Install this backend as the logger of the `log` crate, and set the maximum level of the `log` crate to the level of this backend.
+    pub(crate) fn register(self) -> Result<(), PipeLoggerBuilderError> {
+        let level = self.level;
+
+        log::set_boxed_logger(Box::new(self))
+            .map_err(|_| PipeLoggerBuilderError::LogLoggerAlreadyInitialized)?;
+
+        log::set_max_level(level);
+
+        Ok(())
+    }
+
+    fn format_record(&self, logger: &PipeLogger, record: &Record) -> String {
+        let mut line = String::with_capacity(self.format.len() + 64);
+        let mut rest = self.format.as_str();
+
+        while let Some(start) = rest.find('{') {
+            line.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let end = match rest.find('}') {
+                Some(end) => end,
+                None => break,
+            };
+
+            match &rest[1..end] {
+                "timestamp" => {
+                    line.push_str(&(logger.clock)().to_rfc3339_opts(SecondsFormat::Millis, true))
+                },
+                "level" => line.push_str(record.level().as_str()),
+                "target" => line.push_str(record.target()),
+                "message" => line.push_str(&record.args().to_string()),
+                _ => line.push_str(&rest[..=end]),
+            }
+
+            rest = &rest[end + 1..];
+        }
+
+        line.push_str(rest);
+
+        line
+    }
+}
+
+impl Log for PipeLoggerBackend {
+    #[inline]
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        // a logger broken by a panic is given up
+        if let Ok(mut logger) = self.logger.lock() {
+            let line = self.format_record(&logger, record);
+
+            if let Err(err) = logger.write_line(line) {
+                eprintln!("{}", err);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut logger) = self.logger.lock() {
+            if let Err(err) = std::io::Write::flush(&mut *logger) {
+                eprintln!("{}", err);
+            }
+        }
+    }
+}
+
+impl<P: AsRef<Path>> PipeLoggerBuilder<P> {
+    /// Build a `PipeLogger` and install it as the logger of the `log` crate at `level`, in the `DEFAULT_RECORD_FORMAT`, and as the process-global logger. Use `PipeLoggerBackend` for another record format.
+    #[inline]
+    pub fn init_log(self, level: LevelFilter) -> Result<(), PipeLoggerBuilderError> {
+        PipeLoggerBackend::new(self.build()?, level).init()
+    }
+}
@@ -0,0 +1,115 @@
+use std::time::Instant;
+
+const WINDOW_SECONDS: usize = 300;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+/// The average numbers of lines and bytes written per second during a period.
+pub struct Rate {
+    /// Lines per second.
+    pub lines: f64,
+    /// Bytes per second.
+    pub bytes: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+/// The rolling write rates of a `PipeLogger`.
+pub struct LogRates {
+    /// The rate during the last second.
+    pub last_second:       Rate,
+    /// The rate during the last minute.
+    pub last_minute:       Rate,
+    /// The rate during the last five minutes.
+    pub last_five_minutes: Rate,
+}
+
+/// Counts the written lines and bytes in one-second buckets for the last five minutes.
+#[derive(Debug)]
+pub(crate) struct RateCounter {
+    epoch:   Instant,
+    second:  u64,
+    buckets: Box<[(u64, u64)]>,
+}
+
+impl RateCounter {
+    pub(crate) fn new() -> RateCounter {
+        RateCounter {
+            epoch:   Instant::now(),
+            second:  0,
+            buckets: vec![(0, 0); WINDOW_SECONDS].into(),
+        }
+    }
+
+    /// Move to the current second, clearing the buckets of the seconds which have passed.
+    fn advance(&mut self) -> u64 {
+        let second = self.epoch.elapsed().as_secs();
+
+        if second > self.second {
+            let passed = (second - self.second).min(WINDOW_SECONDS as u64);
+
+            for i in 1..=passed {
+                self.buckets[((self.second + i) % WINDOW_SECONDS as u64) as usize] = (0, 0);
+            }
+
+            self.second = second;
+        }
+
+        second
+    }
+
+    /// Count some lines and bytes in the current second. If a new second has begun, returns the previous second in which something was recorded.
+    pub(crate) fn record(&mut self, lines: u64, bytes: u64) -> Option<u64> {
+        let previous_second = self.second;
+
+        let second = self.advance();
+
+        let bucket = &mut self.buckets[(second % WINDOW_SECONDS as u64) as usize];
+
+        bucket.0 += lines;
+        bucket.1 += bytes;
+
+        if second > previous_second {
+            Some(previous_second)
+        } else {
+            None
+        }
+    }
+
+    /// The numbers of lines and bytes written during a second which has been recorded in the last five minutes.
+    pub(crate) fn second_rate(&self, second: u64) -> Rate {
+        let bucket = self.buckets[(second % WINDOW_SECONDS as u64) as usize];
+
+        Rate {
+            lines: bucket.0 as f64, bytes: bucket.1 as f64
+        }
+    }
+
+    /// The current second since this counter was created.
+    pub(crate) fn second(&self) -> u64 {
+        self.second
+    }
+
+    /// The rate during the last `seconds` seconds, including the current one.
+    fn rate(&self, now: u64, seconds: usize) -> Rate {
+        let (lines, bytes) = (0..seconds as u64)
+            .take_while(|i| *i <= now)
+            .map(|i| now - i)
+            // nothing has been recorded after `self.second`, and those buckets are outdated
+            .filter(|second| *second <= self.second)
+            .map(|second| self.buckets[(second % WINDOW_SECONDS as u64) as usize])
+            .fold((0, 0), |(lines, bytes), bucket| (lines + bucket.0, bytes + bucket.1));
+
+        Rate {
+            lines: lines as f64 / seconds as f64, bytes: bytes as f64 / seconds as f64
+        }
+    }
+
+    pub(crate) fn rates(&self) -> LogRates {
+        let now = self.epoch.elapsed().as_secs();
+
+        LogRates {
+            last_second:       self.rate(now, 1),
+            last_minute:       self.rate(now, 60),
+            last_five_minutes: self.rate(now, WINDOW_SECONDS),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDateTime;
+
+use crate::{compression_method::strip_compressed_extension, split_log_path, NameSplit};
+
+/// Rename the rotated (and compressed) log files of the log file at `log_path` from a previous naming scheme to another one. A template is a `chrono` format string of the part between the file stem and the extension, such as `-%Y-%m-%d-%H-%M-%S-%3f` which is used by `PipeLogger`. Files which already exist with the new names are left untouched. Returns the new paths.
+#[inline]
+pub fn migrate_naming<P: AsRef<Path>>(
+    log_path: P,
+    old_template: &str,
+    new_template: &str,
+) -> io::Result<Vec<PathBuf>> {
+    migrate_naming_with_name_split(log_path, &NameSplit::default(), old_template, new_template)
+}
+
+/// Same as `migrate_naming`, but the file name of the log file is split by `name_split`.
+pub fn migrate_naming_with_name_split<P: AsRef<Path>>(
+    log_path: P,
+    name_split: &NameSplit,
+    old_template: &str,
+    new_template: &str,
+) -> io::Result<Vec<PathBuf>> {
+    let (_, folder_path, file_name, file_name_point_index) =
+        split_log_path(log_path.as_ref(), name_split)?;
+
+    let stem = &file_name[..file_name_point_index];
+    let ext = &file_name[file_name_point_index..];
+
+    let mut migrated_paths = Vec::new();
+
+    for entry in folder_path.read_dir()?.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+
+        if !path.is_file() {
+            continue;
+        }
+
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let (name, compressed_extension) = strip_compressed_extension(name).unwrap_or((name, ""));
+
+        let middle = match name.strip_prefix(stem).and_then(|name| name.strip_suffix(ext)) {
+            Some(middle) => middle,
+            None => continue,
+        };
+
+        let datetime = match NaiveDateTime::parse_from_str(middle, old_template) {
+            Ok(datetime) => datetime,
+            Err(_) => continue,
+        };
+
+        let new_middle = datetime.format(new_template).to_string();
+
+        if new_middle == middle {
+            continue;
+        }
+
+        let new_name = format!("{}{}{}{}", stem, new_middle, ext, compressed_extension);
+
+        let new_path = Path::join(&folder_path, new_name);
+
+        if new_path.exists() {
+            continue;
+        }
+
+        fs::rename(&path, &new_path)?;
+
+        migrated_paths.push(new_path);
+    }
+
+    migrated_paths.sort_unstable();
+
+    Ok(migrated_paths)
+}
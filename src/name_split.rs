@@ -0,0 +1,87 @@
+use std::io;
+
+/// The maximum length in bytes of a file name on most file systems.
+const MAX_FILE_NAME_LENGTH: usize = 255;
+
+/// The room for `-<timestamp>-<sequence>` and `.xz` in the names of rotated log files.
+const ROTATED_SUFFIX_LENGTH: usize = 48;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// How to split the file name of a log file into the stem and the extension. The names of the rotated log files are `<stem>-<timestamp><extension>`. A leading dot (e.g. `.env.log`) is always a part of the stem.
+pub enum NameSplit {
+    /// Split at the first dot, e.g. `mylog` + `.txt.gz` for `mylog.txt.gz`.
+    FirstDot,
+    /// Split at the last dot, e.g. `mylog.txt` + `.gz` for `mylog.txt.gz`.
+    LastDot,
+    /// Treat the whole file name as the stem.
+    NoExtension,
+    /// Use the given stem and extension, whose concatenation must be the file name.
+    Explicit { stem: String, ext: String },
+}
+
+impl Default for NameSplit {
+    #[inline]
+    fn default() -> Self {
+        NameSplit::LastDot
+    }
+}
+
+impl NameSplit {
+    /// Get the index where the extension begins in `file_name`.
+    pub(crate) fn split_index(&self, file_name: &str) -> io::Result<usize> {
+        // a dotfile such as `.env` has no extension
+        let offset = if file_name.starts_with('.') { 1 } else { 0 };
+
+        let index = match self {
+            NameSplit::FirstDot => file_name[offset..].find('.').map(|index| index + offset),
+            NameSplit::LastDot => file_name[offset..].rfind('.').map(|index| index + offset),
+            NameSplit::NoExtension => None,
+            NameSplit::Explicit {
+                stem,
+                ext,
+            } => {
+                if file_name.strip_prefix(stem.as_str()) == Some(ext.as_str()) {
+                    Some(stem.len())
+                } else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "{:?} is not the concatenation of {:?} and {:?}.",
+                            file_name, stem, ext
+                        ),
+                    ));
+                }
+            },
+        };
+
+        Ok(index.unwrap_or(file_name.len()))
+    }
+}
+
+/// Get the file name (and the index of its extension) from which the names of the rotated log files are derived. If `<stem>-<timestamp><extension>.xz` may exceed `MAX_FILE_NAME_LENGTH`, the stem is truncated and followed by `~` and the FNV-1a hash of the whole stem, so that the names are still unique and every logger of the same log file derives the same ones.
+pub(crate) fn rotated_base_name(file_name: &str, file_name_point_index: usize) -> (String, usize) {
+    let stem = &file_name[..file_name_point_index];
+    let ext = &file_name[file_name_point_index..];
+
+    if file_name.len() + ROTATED_SUFFIX_LENGTH <= MAX_FILE_NAME_LENGTH {
+        return (file_name.to_string(), file_name_point_index);
+    }
+
+    let hash = stem
+        .bytes()
+        .fold(0xCBF29CE484222325u64, |hash, b| (hash ^ u64::from(b)).wrapping_mul(0x100000001B3));
+
+    let hash = format!("~{:016x}", hash);
+
+    // the extension is never truncated
+    let mut keep =
+        MAX_FILE_NAME_LENGTH.saturating_sub(ROTATED_SUFFIX_LENGTH + ext.len() + hash.len());
+
+    while !stem.is_char_boundary(keep) {
+        keep -= 1;
+    }
+
+    let stem = format!("{}{}", &stem[..keep], hash);
+
+    (format!("{}{}", stem, ext), stem.len())
+}
@@ -0,0 +1,223 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufRead, BufReader, ErrorKind},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+
+use crate::{
+    compression_method::COMPRESSED_EXTENSIONS, parse_rotated_log_file_timestamp,
+    scan_rotated_log_file_names, split_log_path, CompressionMethod, NameSplit, RotationNaming,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A line found by `PipeLogSearcher::grep`.
+pub struct Match {
+    /// The path of the file which contains the line.
+    pub path:        PathBuf,
+    /// The number of the line in the file, starting from 1.
+    pub line_number: usize,
+    /// The line, without the new line character.
+    pub line:        String,
+    /// The time when the file was rotated. It is `None` for the active log file.
+    pub timestamp:   Option<DateTime<Utc>>,
+}
+
+/// Searches a log file and its rotated (and compressed) log files.
+#[derive(Debug, Clone)]
+pub struct PipeLogSearcher {
+    file_path:             PathBuf,
+    folder_path:           PathBuf,
+    file_name:             String,
+    file_name_point_index: usize,
+}
+
+impl PipeLogSearcher {
+    /// Create a new `PipeLogSearcher` for the log file at `log_path`.
+    #[inline]
+    pub fn new<P: AsRef<Path>>(log_path: P) -> io::Result<PipeLogSearcher> {
+        Self::with_name_split(log_path, &NameSplit::default())
+    }
+
+    /// Create a new `PipeLogSearcher` for the log file at `log_path`, whose file name is split by `name_split`.
+    pub fn with_name_split<P: AsRef<Path>>(
+        log_path: P,
+        name_split: &NameSplit,
+    ) -> io::Result<PipeLogSearcher> {
+        let (file_path, folder_path, file_name, file_name_point_index) =
+            split_log_path(log_path.as_ref(), name_split)?;
+
+        Ok(PipeLogSearcher {
+            file_path,
+            folder_path,
+            file_name,
+            file_name_point_index,
+        })
+    }
+
+    /// Find the lines matching the regular expression `pattern`, from the oldest rotated log file to the active log file. If `time_range` is set, only the files which may contain lines written within the range are searched.
+    pub fn grep(
+        &self,
+        pattern: &str,
+        time_range: Option<Range<DateTime<Utc>>>,
+    ) -> io::Result<impl Iterator<Item = io::Result<Match>>> {
+        let regex =
+            Regex::new(pattern).map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))?;
+
+        let rotated_log_file_names = scan_rotated_log_file_names(
+            &self.folder_path,
+            &self.file_name,
+            self.file_name_point_index,
+            &RotationNaming::Timestamp,
+        )?;
+
+        let mut files = VecDeque::with_capacity(rotated_log_file_names.len() + 1);
+
+        // a file rotated at T(n) contains the lines written after T(n - 1) and until T(n)
+        let mut previous_timestamp: Option<DateTime<Utc>> = None;
+
+        for rotated_log_file_name in rotated_log_file_names {
+            let timestamp = parse_rotated_log_file_timestamp(
+                &rotated_log_file_name,
+                self.file_name_point_index,
+            );
+
+            let in_range = match (&time_range, timestamp) {
+                (Some(time_range), Some(timestamp)) => {
+                    timestamp >= time_range.start
+                        && previous_timestamp.map(|t| t < time_range.end).unwrap_or(true)
+                },
+                _ => true,
+            };
+
+            if timestamp.is_some() {
+                previous_timestamp = timestamp;
+            }
+
+            if !in_range {
+                continue;
+            }
+
+            let path = self.folder_path.join(&rotated_log_file_name);
+
+            if path.is_file() {
+                files.push_back((path, None, timestamp));
+            } else {
+                for extension in COMPRESSED_EXTENSIONS {
+                    let path =
+                        self.folder_path.join(format!("{}{}", rotated_log_file_name, extension));
+
+                    if path.is_file() {
+                        files.push_back((path, Some(extension), timestamp));
+
+                        break;
+                    }
+                }
+            }
+        }
+
+        let active_in_range = match (&time_range, previous_timestamp) {
+            (Some(time_range), Some(previous_timestamp)) => previous_timestamp < time_range.end,
+            _ => true,
+        };
+
+        if active_in_range {
+            files.push_back((self.file_path.clone(), None, None));
+        }
+
+        Ok(Matches {
+            regex,
+            files,
+            current: None,
+        })
+    }
+}
+
+/// (the path, the extension if it is compressed, the timestamp)
+type PendingFile = (PathBuf, Option<&'static str>, Option<DateTime<Utc>>);
+
+type CurrentFile = (PathBuf, Option<DateTime<Utc>>, Box<dyn BufRead>, usize);
+
+struct Matches {
+    regex:   Regex,
+    files:   VecDeque<PendingFile>,
+    current: Option<CurrentFile>,
+}
+
+impl Iterator for Matches {
+    type Item = io::Result<Match>;
+
+    fn next(&mut self) -> Option<io::Result<Match>> {
+        let mut buffer = Vec::new();
+
+        loop {
+            if self.current.is_none() {
+                let (path, compressed, timestamp) = self.files.pop_front()?;
+
+                let file = match File::open(&path) {
+                    Ok(file) => file,
+                    // the file may have been removed by the logger after scanning
+                    Err(ref err) if err.kind() == ErrorKind::NotFound => continue,
+                    Err(err) => return Some(Err(err)),
+                };
+
+                let reader: Box<dyn BufRead> = match compressed {
+                    Some(extension) => {
+                        let decoder = match CompressionMethod::from_extension(extension) {
+                            Some(method) => method.decoder(file),
+                            None => Err(io::Error::new(
+                                ErrorKind::Unsupported,
+                                format!("The feature to decompress {:?} is disabled.", path),
+                            )),
+                        };
+
+                        match decoder {
+                            Ok(decoder) => Box::new(BufReader::new(decoder)),
+                            Err(err) => return Some(Err(err)),
+                        }
+                    },
+                    None => Box::new(BufReader::new(file)),
+                };
+
+                self.current = Some((path, timestamp, reader, 0));
+            }
+
+            let (path, timestamp, reader, line_number) = self.current.as_mut().unwrap();
+
+            buffer.clear();
+
+            match reader.read_until(b'\n', &mut buffer) {
+                Ok(0) => {
+                    self.current = None;
+                },
+                Ok(_) => {
+                    *line_number += 1;
+
+                    if buffer.ends_with(b"\n") {
+                        buffer.pop();
+                    }
+
+                    let line = String::from_utf8_lossy(&buffer);
+
+                    if self.regex.is_match(&line) {
+                        return Some(Ok(Match {
+                            path:        path.clone(),
+                            line_number: *line_number,
+                            line:        line.into_owned(),
+                            timestamp:   *timestamp,
+                        }));
+                    }
+                },
+                Err(err) => {
+                    self.current = None;
+
+                    return Some(Err(err));
+                },
+            }
+        }
+    }
+}
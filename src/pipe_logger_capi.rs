@@ -0,0 +1,138 @@
+/*!
+A minimal C API of `PipeLogger`, enabled by the `capi` feature. The declarations are in `include/pipe_logger.h`.
+
+To build a shared library for C, run `cargo rustc --release --features capi --crate-type cdylib`. Strings passed to these functions need to be NUL-terminated UTF-8. A panic never unwinds into C; it is returned as a failure.
+*/
+
+use std::{
+    ffi::CStr,
+    os::raw::c_char,
+    panic::{self, AssertUnwindSafe},
+    ptr,
+    time::Duration,
+};
+
+use crate::{PipeLogger, PipeLoggerBuilder, RotateMethod};
+
+/// Run the body of a function of the C API. Returns `on_panic` if it panics, because unwinding into C is undefined behavior.
+#[inline]
+fn catch_panic<T, F: FnOnce() -> T>(on_panic: T, f: F) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(on_panic)
+}
+
+/// Convert a C string to `&str`. Returns `None` if it is null or not UTF-8.
+unsafe fn to_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        None
+    } else {
+        CStr::from_ptr(s).to_str().ok()
+    }
+}
+
+/// Build a `PipeLogger`. `rotate_file_size` is the rotation size in bytes (`0` to disable the rotation), `count` is the maximum number of log files (`0` for no limit), and `compress` is whether to compress the rotated log files (non-zero to enable). Returns null on failure. The logger needs to be released by `pipe_logger_close`.
+///
+/// # Safety
+///
+/// `log_path` needs to be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn pipe_logger_new(
+    log_path: *const c_char,
+    rotate_file_size: u64,
+    count: usize,
+    compress: i32,
+) -> *mut PipeLogger {
+    catch_panic(ptr::null_mut(), || {
+        let log_path = match to_str(log_path) {
+            Some(log_path) => log_path,
+            None => return ptr::null_mut(),
+        };
+
+        let mut builder = PipeLoggerBuilder::new(log_path);
+
+        builder
+            .set_rotate(if rotate_file_size == 0 {
+                None
+            } else {
+                Some(RotateMethod::FileSize(rotate_file_size))
+            })
+            .set_count(if count == 0 { None } else { Some(count) })
+            .set_compress(compress != 0);
+
+        match builder.build() {
+            Ok(logger) => Box::into_raw(Box::new(logger)),
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+/// Write a string with a new line. Returns `1` if the log is rotated, `0` if not, or `-1` on failure.
+///
+/// # Safety
+///
+/// `logger` needs to be null or a pointer returned by `pipe_logger_new` which has not been closed, and `line` needs to be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn pipe_logger_write_line(
+    logger: *mut PipeLogger,
+    line: *const c_char,
+) -> i32 {
+    catch_panic(-1, || {
+        let logger = match logger.as_mut() {
+            Some(logger) => logger,
+            None => return -1,
+        };
+
+        let line = match to_str(line) {
+            Some(line) => line,
+            None => return -1,
+        };
+
+        match logger.write_line(line) {
+            Ok(Some(_)) => 1,
+            Ok(None) => 0,
+            Err(_) => -1,
+        }
+    })
+}
+
+/// Rotate the log file now. Returns `1` if the log is rotated, `0` if the log file is empty, or `-1` on failure.
+///
+/// # Safety
+///
+/// `logger` needs to be null or a pointer returned by `pipe_logger_new` which has not been closed.
+#[no_mangle]
+pub unsafe extern "C" fn pipe_logger_rotate(logger: *mut PipeLogger) -> i32 {
+    catch_panic(-1, || {
+        let logger = match logger.as_mut() {
+            Some(logger) => logger,
+            None => return -1,
+        };
+
+        match logger.rotate() {
+            Ok(Some(_)) => 1,
+            Ok(None) => 0,
+            Err(_) => -1,
+        }
+    })
+}
+
+/// Sync the log file, wait for the pending compressions for at most `deadline_millis` milliseconds, and release the logger. Returns `0` if everything was finished, or `-1` if not.
+///
+/// # Safety
+///
+/// `logger` needs to be null or a pointer returned by `pipe_logger_new` which has not been closed. It must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn pipe_logger_close(logger: *mut PipeLogger, deadline_millis: u64) -> i32 {
+    catch_panic(-1, || {
+        if logger.is_null() {
+            return -1;
+        }
+
+        let logger = Box::from_raw(logger);
+
+        if logger.close(Duration::from_millis(deadline_millis)).is_complete() {
+            0
+        } else {
+            -1
+        }
+    })
+}
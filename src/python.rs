@@ -0,0 +1,207 @@
+/*!
+Python bindings of `PipeLoggerBuilder` and `PipeLogger`, enabled by the `python` feature. The rotated log files are named in the same way as the ones of Rust programs.
+
+To build an extension module, enable the `extension-module` feature of `pyo3` as well, e.g. `maturin build --features python,pyo3/extension-module`.
+
+```python
+from pipe_logger_lib import PipeLoggerBuilder
+
+builder = PipeLoggerBuilder("mylog.txt")
+builder.set_rotate_file_size(1024 * 1024).set_count(10).set_compress(True)
+
+logger = builder.build()
+logger.write_line("Hello world!")
+logger.close(10.0)
+```
+*/
+
+use std::{
+    io,
+    path::PathBuf,
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
+
+use pyo3::{
+    exceptions::{PyOSError, PyValueError},
+    prelude::*,
+};
+
+use crate::{PipeLoggerBuilderError, RotateMethod, Tee};
+
+fn to_py_err(err: io::Error) -> PyErr {
+    PyOSError::new_err(err.to_string())
+}
+
+/// Convert seconds to `Duration`.
+fn to_duration(secs: f64) -> PyResult<Duration> {
+    if secs.is_finite() && secs >= 0.0 && secs <= u64::MAX as f64 {
+        Ok(Duration::from_secs_f64(secs))
+    } else {
+        Err(PyValueError::new_err(format!("{} is not a valid number of seconds.", secs)))
+    }
+}
+
+fn to_py_builder_err(err: PipeLoggerBuilderError) -> PyErr {
+    match err {
+        PipeLoggerBuilderError::IOError(err) => to_py_err(err),
+        _ => PyValueError::new_err(err.to_string()),
+    }
+}
+
+/// To build a PipeLogger instance. The setters return the builder itself so that they can be chained.
+#[pyclass(name = "PipeLoggerBuilder")]
+pub struct PyPipeLoggerBuilder {
+    builder: crate::PipeLoggerBuilder<PathBuf>,
+}
+
+#[pymethods]
+impl PyPipeLoggerBuilder {
+    #[new]
+    fn new(log_path: PathBuf) -> Self {
+        PyPipeLoggerBuilder {
+            builder: crate::PipeLoggerBuilder::new(log_path)
+        }
+    }
+
+    /// The rotation size in bytes, or `None` to disable the rotation.
+    #[pyo3(signature = (rotate_file_size))]
+    fn set_rotate_file_size(
+        mut slf: PyRefMut<Self>,
+        rotate_file_size: Option<u64>,
+    ) -> PyRefMut<Self> {
+        slf.builder.set_rotate(rotate_file_size.map(RotateMethod::FileSize));
+        slf
+    }
+
+    #[pyo3(signature = (count))]
+    fn set_count(mut slf: PyRefMut<Self>, count: Option<usize>) -> PyRefMut<Self> {
+        slf.builder.set_count(count);
+        slf
+    }
+
+    fn set_compress(mut slf: PyRefMut<Self>, compress: bool) -> PyRefMut<Self> {
+        slf.builder.set_compress(compress);
+        slf
+    }
+
+    fn set_compression_threads(
+        mut slf: PyRefMut<Self>,
+        compression_threads: usize,
+    ) -> PyRefMut<Self> {
+        slf.builder.set_compression_threads(compression_threads);
+        slf
+    }
+
+    /// The minimum age of the log file in seconds before it can be rotated.
+    fn set_min_file_age_before_rotation(
+        mut slf: PyRefMut<Self>,
+        min_file_age: f64,
+    ) -> PyResult<PyRefMut<Self>> {
+        slf.builder.set_min_file_age_before_rotation(to_duration(min_file_age)?);
+
+        Ok(slf)
+    }
+
+    fn set_rotation_sequence(mut slf: PyRefMut<Self>, rotation_sequence: bool) -> PyRefMut<Self> {
+        slf.builder.set_rotation_sequence(rotation_sequence);
+        slf
+    }
+
+    fn set_lock(mut slf: PyRefMut<Self>, lock: bool) -> PyRefMut<Self> {
+        slf.builder.set_lock(lock);
+        slf
+    }
+
+    /// `"stdout"`, `"stderr"`, or `None`.
+    #[pyo3(signature = (tee))]
+    fn set_tee(mut slf: PyRefMut<Self>, tee: Option<String>) -> PyResult<PyRefMut<Self>> {
+        let tee = match tee.as_deref() {
+            Some("stdout") => Some(Tee::Stdout),
+            Some("stderr") => Some(Tee::Stderr),
+            Some(tee) => {
+                return Err(PyValueError::new_err(format!("{:?} is not a valid tee.", tee)))
+            },
+            None => None,
+        };
+
+        slf.builder.set_tee(tee);
+
+        Ok(slf)
+    }
+
+    /// Build a new PipeLogger.
+    fn build(&self) -> PyResult<PyPipeLogger> {
+        let logger = self.builder.clone().build().map_err(to_py_builder_err)?;
+
+        Ok(PyPipeLogger {
+            logger: Mutex::new(Some(logger))
+        })
+    }
+}
+
+/// PipeLogger can help you stores, rotates and compresses logs. It can be shared by Python threads. The methods raise `ValueError` after the logger is closed.
+#[pyclass(name = "PipeLogger")]
+pub struct PyPipeLogger {
+    logger: Mutex<Option<crate::PipeLogger>>,
+}
+
+impl PyPipeLogger {
+    /// Run `f` on the logger with the GIL released, so that waiting for the lock and the file system does not block other Python threads.
+    fn with_logger<T: Send>(
+        &self,
+        py: Python<'_>,
+        f: impl FnOnce(&mut crate::PipeLogger) -> io::Result<T> + Send,
+    ) -> PyResult<T> {
+        py.allow_threads(|| match self.lock()?.as_mut() {
+            Some(logger) => f(logger).map_err(to_py_err),
+            None => Err(PyValueError::new_err("The logger is closed.")),
+        })
+    }
+
+    fn lock(&self) -> PyResult<MutexGuard<'_, Option<crate::PipeLogger>>> {
+        self.logger.lock().map_err(|_| PyValueError::new_err("The logger is broken by a panic."))
+    }
+}
+
+#[pymethods]
+impl PyPipeLogger {
+    /// Write a string. If the log is rotated, this method returns the renamed path.
+    fn write(&self, py: Python<'_>, text: &str) -> PyResult<Option<PathBuf>> {
+        self.with_logger(py, |logger| logger.write(text))
+    }
+
+    /// Write a string with a new line. If the log is rotated, this method returns the renamed path.
+    fn write_line(&self, py: Python<'_>, text: &str) -> PyResult<Option<PathBuf>> {
+        self.with_logger(py, |logger| logger.write_line(text))
+    }
+
+    /// Rotate the log file now, regardless of its size. Nothing is done if the log file is empty. Returns the renamed path.
+    fn rotate(&self, py: Python<'_>) -> PyResult<Option<PathBuf>> {
+        self.with_logger(py, |logger| logger.rotate())
+    }
+
+    /// Sync the log file and wait for the pending compressions for at most `deadline` seconds, then close this logger. Returns whether everything was finished before the deadline.
+    fn close(&self, py: Python<'_>, deadline: f64) -> PyResult<bool> {
+        let deadline = to_duration(deadline)?;
+
+        // the GIL is released while waiting for the pending compressions
+        py.allow_threads(|| {
+            let logger = self
+                .lock()?
+                .take()
+                .ok_or_else(|| PyValueError::new_err("The logger is closed."))?;
+
+            Ok(logger.close(deadline).is_complete())
+        })
+    }
+}
+
+/// The `pipe_logger_lib` Python module.
+#[pymodule]
+pub fn pipe_logger_lib(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyPipeLoggerBuilder>()?;
+    m.add_class::<PyPipeLogger>()?;
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// What is wrong with the log file reopened after a rotation.
+pub enum ReopenMismatchReason {
+    /// The handle is not the file at the path of the log file (e.g. it is still the rotated copy).
+    Identity,
+    /// The file is not empty after being truncated, when the log file has been copied instead of renamed.
+    NotEmpty,
+    /// The handle is not in the append mode.
+    NotAppending,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Raised when the log file reopened after a rotation fails the verification of `PipeLoggerBuilder::set_verify_reopen`.
+pub struct ReopenMismatch {
+    /// The path of the log file.
+    pub path:      PathBuf,
+    pub reason:    ReopenMismatchReason,
+    /// Whether reopening the log file again has passed the verification.
+    pub recovered: bool,
+}
+
+pub(crate) type ReopenMismatchCallback = Box<dyn Fn(ReopenMismatch) + Send>;
+
+/// Verify that `file` is the log file at `path` rather than the rotated log file at `rotated_path`, opened for appending, and also empty if `expect_empty` is `true`.
+pub(crate) fn check_reopened_log_file(
+    path: &Path,
+    file: &File,
+    rotated_path: &Path,
+    expect_empty: bool,
+) -> io::Result<Option<ReopenMismatchReason>> {
+    let metadata = file.metadata()?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::{fs::MetadataExt, io::AsRawFd};
+
+        if is_log_file_moved(path, file)? {
+            return Ok(Some(ReopenMismatchReason::Identity));
+        }
+
+        let rotated_metadata = fs::metadata(rotated_path)?;
+
+        if metadata.dev() == rotated_metadata.dev() && metadata.ino() == rotated_metadata.ino() {
+            return Ok(Some(ReopenMismatchReason::Identity));
+        }
+
+        let flags = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETFL) };
+
+        if flags < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        if flags & libc::O_APPEND == 0 {
+            return Ok(Some(ReopenMismatchReason::NotAppending));
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = rotated_path;
+
+        if !fs::metadata(path)?.is_file() {
+            return Ok(Some(ReopenMismatchReason::Identity));
+        }
+    }
+
+    if expect_empty && metadata.len() != 0 {
+        return Ok(Some(ReopenMismatchReason::NotEmpty));
+    }
+
+    Ok(None)
+}
+
+/// Whether the path of the log file no longer refers to `file`, because the file has been moved or deleted. Only a deleted file is detected on systems other than Unix-like ones.
+pub(crate) fn is_log_file_moved(path: &Path, file: &File) -> io::Result<bool> {
+    let metadata_on_disk = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(true),
+        Err(err) => return Err(err),
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let metadata = file.metadata()?;
+
+        Ok(metadata.dev() != metadata_on_disk.dev() || metadata.ino() != metadata_on_disk.ino())
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (file, metadata_on_disk);
+
+        Ok(false)
+    }
+}
@@ -1,6 +1,96 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Duration as ChronoDuration, Local, Offset, TimeZone, Utc};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-/// The way to rotate log files.
+/// The way to rotate log files. The time-based methods follow the clock of the logger, in local time if `PipeLoggerBuilder::set_local_time` is `true` or in UTC, and rotate the log file lazily on the first write after the time threshold, so an idle log file is not rotated until something is written.
 pub enum RotateMethod {
     /// Rotate log files by a file size threshold in bytes.
     FileSize(u64),
+    /// Rotate log files by a time interval since the last rotation (or since the logger was built).
+    Interval(Duration),
+    /// Rotate log files on the hour.
+    Hourly,
+    /// Rotate log files at midnight.
+    Daily,
+    /// Rotate log files when either the file size threshold in bytes or the time interval is hit.
+    FileSizeOrInterval(u64, Duration),
+    /// Rotate log files when either the file size threshold in bytes is hit, or on the hour.
+    FileSizeOrHourly(u64),
+    /// Rotate log files when either the file size threshold in bytes is hit, or at midnight.
+    FileSizeOrDaily(u64),
+}
+
+impl RotateMethod {
+    /// The file size threshold in bytes, if any.
+    #[inline]
+    pub fn file_size(&self) -> Option<u64> {
+        match self {
+            RotateMethod::FileSize(size)
+            | RotateMethod::FileSizeOrInterval(size, _)
+            | RotateMethod::FileSizeOrHourly(size)
+            | RotateMethod::FileSizeOrDaily(size) => Some(*size),
+            _ => None,
+        }
+    }
+
+    /// The time interval, if any.
+    #[inline]
+    pub fn interval(&self) -> Option<Duration> {
+        match self {
+            RotateMethod::Interval(interval) | RotateMethod::FileSizeOrInterval(_, interval) => {
+                Some(*interval)
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether the log files are rotated by time.
+    #[inline]
+    pub fn is_time_based(&self) -> bool {
+        !matches!(self, RotateMethod::FileSize(_))
+    }
+
+    /// The time after which the log file opened (or last rotated) at `since` needs to be rotated. The hours and days are in local time if `local_time` is `true`, or in UTC. Returns `None` if the log files are not rotated by time.
+    pub(crate) fn next_rotation_time(
+        &self,
+        since: DateTime<Utc>,
+        local_time: bool,
+    ) -> Option<DateTime<Utc>> {
+        match self {
+            RotateMethod::FileSize(_) => None,
+            RotateMethod::Interval(interval) | RotateMethod::FileSizeOrInterval(_, interval) => {
+                ChronoDuration::from_std(*interval)
+                    .ok()
+                    .and_then(|interval| since.checked_add_signed(interval))
+            },
+            RotateMethod::Hourly | RotateMethod::FileSizeOrHourly(_) => {
+                next_boundary(since, 60 * 60, local_time)
+            },
+            RotateMethod::Daily | RotateMethod::FileSizeOrDaily(_) => {
+                next_boundary(since, 24 * 60 * 60, local_time)
+            },
+        }
+    }
+}
+
+/// The first multiple of `period` seconds (since the Unix epoch, in local time if `local_time` is `true`) after `since`.
+fn next_boundary(since: DateTime<Utc>, period: i64, local_time: bool) -> Option<DateTime<Utc>> {
+    if !local_time {
+        let boundary = (since.timestamp().div_euclid(period) + 1).checked_mul(period)?;
+
+        return Utc.timestamp_opt(boundary, 0).single();
+    }
+
+    let offset = since.with_timezone(&Local).offset().fix().local_minus_utc() as i64;
+
+    let boundary = ((since.timestamp() + offset).div_euclid(period) + 1).checked_mul(period)?;
+
+    let naive = Utc.timestamp_opt(boundary, 0).single()?.naive_utc();
+
+    match Local.from_local_datetime(&naive).earliest() {
+        Some(datetime) => Some(datetime.with_timezone(&Utc)),
+        // skipped by a daylight saving time transition
+        None => Utc.timestamp_opt(boundary - offset, 0).single(),
+    }
 }
@@ -0,0 +1,192 @@
+use std::cmp::Reverse;
+
+use chrono::{
+    format::{parse, Parsed, StrftimeItems},
+    DateTime, Local, NaiveDateTime, TimeZone, Utc,
+};
+
+use crate::{parse_rotated_log_file_timestamp, ROTATED_LOG_FILE_TIMESTAMP_PATTERNS};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// How to name the rotated log files. Whatever the scheme is, the existing rotated log files are found by it, so the retention keeps working across restarts.
+pub enum RotationNaming {
+    /// `<stem>-<timestamp><extension>` in the `TimestampPrecision`, such as `mylog-2024-01-02-03-04-05-678.txt`.
+    Timestamp,
+    /// `<stem><template><extension>`, where the template is a `chrono` strftime format, such as `.%Y%m%d` for `mylog.20240102.txt`. The supported specifiers are `%Y`, `%y`, `%m`, `%d`, `%j`, `%H`, `%M`, `%S`, `%3f`, `%6f`, `%9f`, `%s` and `%%`. If a name is taken, `-<sequence>` is appended to the formatted template.
+    Template(String),
+    /// `<file name>.<index>` as logrotate does, such as `mylog.txt.1`. The index of the newest rotated log file is `1`, and the older ones are renamed to the next indices on every rotation. The renaming is done in the background after the pending processing of the rotated log files, so the log file is renamed to a hidden pending name first (a pending file left by a crash is renamed when a logger is built). The names have no timestamps, so building a logger with the thinning or `max_age` fails, and neither can `max_total_size` nor `compaction_size` be used.
+    Sequential,
+}
+
+impl Default for RotationNaming {
+    #[inline]
+    fn default() -> Self {
+        RotationNaming::Timestamp
+    }
+}
+
+impl RotationNaming {
+    /// Split the name of a log file, whose extension begins at `file_name_point_index`, into the parts before and after the part varying among its rotated log files.
+    #[inline]
+    pub(crate) fn split<'a>(
+        &self,
+        file_name: &'a str,
+        file_name_point_index: usize,
+    ) -> (&'a str, &'a str) {
+        match self {
+            RotationNaming::Sequential => (file_name, ""),
+            _ => file_name.split_at(file_name_point_index),
+        }
+    }
+
+    /// The regular expression of the part varying among the rotated log files. Returns an error message if the template is invalid.
+    pub(crate) fn pattern(&self) -> Result<String, String> {
+        match self {
+            RotationNaming::Timestamp => {
+                let patterns: Vec<&str> = ROTATED_LOG_FILE_TIMESTAMP_PATTERNS
+                    .iter()
+                    .map(|pattern| &pattern[1..pattern.len() - 1])
+                    .collect();
+
+                Ok(format!("^(?:{})$", patterns.join("|")))
+            },
+            RotationNaming::Template(template) => template_pattern(template),
+            RotationNaming::Sequential => Ok("^\\.[1-9][0-9]*$".to_string()),
+        }
+    }
+
+    /// Whether a part varying among the rotated log files looks like one of this naming, which is some digits after the literal leading the timestamp or the index, even if it does not match the pattern.
+    pub(crate) fn resembles(&self, varying: &str) -> bool {
+        let leading = match self {
+            RotationNaming::Timestamp => "-",
+            RotationNaming::Template(template) => template.split('%').next().unwrap_or(""),
+            RotationNaming::Sequential => ".",
+        };
+
+        varying
+            .strip_prefix(leading)
+            .and_then(|rest| rest.chars().next())
+            .map_or(false, |c| c.is_ascii_digit())
+    }
+
+    /// Parse the part varying among the rotated log files into the time it represents, without its time zone.
+    pub(crate) fn parse_naive(&self, varying: &str) -> Option<NaiveDateTime> {
+        match self {
+            RotationNaming::Timestamp => {
+                parse_rotated_log_file_timestamp(varying, 0).map(|timestamp| timestamp.naive_utc())
+            },
+            RotationNaming::Template(template) => parse_template(varying, template).or_else(|| {
+                let (varying, sequence) = varying.rsplit_once('-')?;
+
+                if sequence.len() >= 3 && sequence.bytes().all(|b| b.is_ascii_digit()) {
+                    parse_template(varying, template)
+                } else {
+                    None
+                }
+            }),
+            RotationNaming::Sequential => None,
+        }
+    }
+
+    /// Parse the part varying among the rotated log files into the time it represents, in local time if `local_time` is `true`, or in UTC.
+    pub(crate) fn parse_timestamp(&self, varying: &str, local_time: bool) -> Option<DateTime<Utc>> {
+        let naive = self.parse_naive(varying)?;
+
+        if local_time {
+            Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|datetime| datetime.with_timezone(&Utc))
+        } else {
+            Some(Utc.from_utc_datetime(&naive))
+        }
+    }
+
+    /// Sort the rotated log files, given as their names and the parts varying among them, from the oldest to the newest.
+    pub(crate) fn sort(&self, rotated_log_file_names: &mut [(String, String)]) {
+        match self {
+            RotationNaming::Timestamp => {
+                // compare the fractional seconds of different lengths by their values rather than their digits
+                rotated_log_file_names.sort_by_cached_key(|(name, timestamp)| {
+                    // a timestamp in seconds has no fraction part
+                    let timestamp = if timestamp.len() == 20 {
+                        format!("{}-", timestamp)
+                    } else {
+                        timestamp.clone()
+                    };
+
+                    (format!("{:0<27}", timestamp), name.clone())
+                })
+            },
+            RotationNaming::Template(_) => {
+                // a longer name with the same time has a sequence number, which is appended later
+                rotated_log_file_names.sort_by_cached_key(|(name, varying)| {
+                    (self.parse_naive(varying), name.len(), name.clone())
+                })
+            },
+            RotationNaming::Sequential => rotated_log_file_names
+                .sort_by_cached_key(|(_, index)| Reverse(index[1..].parse::<u64>().unwrap_or(0))),
+        }
+    }
+}
+
+/// Convert a strftime template to a regular expression, allowing a sequence number to be appended.
+fn template_pattern(template: &str) -> Result<String, String> {
+    let mut pattern = String::from("^");
+
+    let mut chars = template.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '/' || c == '\\' {
+            return Err(format!("The template {:?} contains a path separator.", template));
+        }
+
+        if c != '%' {
+            pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+
+            continue;
+        }
+
+        let specifier = match chars.next() {
+            Some('Y') => "[0-9]{4}",
+            Some('y' | 'm' | 'd' | 'H' | 'M' | 'S') => "[0-9]{2}",
+            Some('j') => "[0-9]{3}",
+            Some('s') => "[0-9]+",
+            Some('%') => "%",
+            Some(digit @ ('3' | '6' | '9')) if chars.next() == Some('f') => match digit {
+                '3' => "[0-9]{3}",
+                '6' => "[0-9]{6}",
+                _ => "[0-9]{9}",
+            },
+            _ => {
+                return Err(format!(
+                    "The template {:?} contains an unsupported specifier.",
+                    template
+                ))
+            },
+        };
+
+        pattern.push_str(specifier);
+    }
+
+    pattern.push_str("(-[0-9]{3,})?$");
+
+    Ok(pattern)
+}
+
+/// Parse a formatted template. The time is midnight if the template has no time.
+fn parse_template(varying: &str, template: &str) -> Option<NaiveDateTime> {
+    let mut parsed = Parsed::new();
+
+    parse(&mut parsed, varying, StrftimeItems::new(template)).ok()?;
+
+    // the parsed fields are kept, since setting a different value fails
+    if !template.contains("%s") {
+        parsed.set_hour(0).ok();
+        parsed.set_minute(0).ok();
+        parsed.set_second(0).ok();
+        parsed.set_nanosecond(0).ok();
+    }
+
+    parsed.to_naive_datetime_with_offset(0).ok()
+}
@@ -0,0 +1,230 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Debug,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{compress_log_file, CompressionLevel, CompressionMethod};
+
+/// A step executed on a rotated log file in a background thread after the log file is rotated, such as compressing or uploading it.
+pub trait RotationProcessor: Debug + Send + Sync {
+    /// Process a rotated log file, and return the path of the processed file which is passed to the next processor. A processor may replace the file with another one, but only the rotated log file and its compressed files (`.xz`, `.gz` and `.zst`) are tracked and removed by the retention.
+    fn process(&self, rotated_log_file: &Path) -> io::Result<PathBuf>;
+
+    /// Process a rotated log file with the metadata attached to its rotation by `PipeLogger::set_next_rotation_meta`. It calls `process` by default.
+    #[inline]
+    fn process_with_meta(
+        &self,
+        rotated_log_file: &Path,
+        _meta: &BTreeMap<String, String>,
+    ) -> io::Result<PathBuf> {
+        self.process(rotated_log_file)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Compress a rotated log file through xz, and remove the uncompressed one. It is the same as `Compression` with `CompressionMethod::Xz`.
+#[deprecated(note = "use `Compression` with `CompressionMethod::Xz` instead")]
+pub struct XzCompression {
+    /// The number of threads used to compress. `0` means using all of the available CPU cores.
+    pub threads: usize,
+    /// The maximum size in bytes of a rotated log file to be kept. Only the tail is stored if the file is bigger than it.
+    pub tail:    Option<u64>,
+    /// The xz preset level.
+    pub level:   CompressionLevel,
+    /// Whether to decode the compressed log file and compare it with the rotated log file before removing the rotated log file.
+    pub verify:  bool,
+}
+
+#[allow(deprecated)]
+impl RotationProcessor for XzCompression {
+    #[inline]
+    fn process(&self, rotated_log_file: &Path) -> io::Result<PathBuf> {
+        Compression {
+            method:  CompressionMethod::Xz,
+            threads: self.threads,
+            tail:    self.tail,
+            level:   self.level,
+            verify:  self.verify,
+        }
+        .process(rotated_log_file)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Compress a rotated log file in a `CompressionMethod`, and remove the uncompressed one. It is the first processor when `PipeLoggerBuilder::set_compress` is enabled.
+pub struct Compression {
+    /// The format to compress.
+    pub method:  CompressionMethod,
+    /// The number of threads used to compress. `0` means using all of the available CPU cores. Only xz can use multiple threads.
+    pub threads: usize,
+    /// The maximum size in bytes of a rotated log file to be kept. Only the tail is stored if the file is bigger than it.
+    pub tail:    Option<u64>,
+    /// The compression level.
+    pub level:   CompressionLevel,
+    /// Whether to decode the compressed log file and compare it with the rotated log file before removing the rotated log file.
+    pub verify:  bool,
+}
+
+impl RotationProcessor for Compression {
+    fn process(&self, rotated_log_file: &Path) -> io::Result<PathBuf> {
+        let mut compressed = rotated_log_file.as_os_str().to_os_string();
+        compressed.push(self.method.extension());
+
+        let compressed = PathBuf::from(compressed);
+
+        compress_log_file(
+            rotated_log_file,
+            &compressed,
+            self.method,
+            self.threads,
+            self.tail,
+            self.level,
+            self.verify,
+        )?;
+
+        Ok(compressed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Announce the path of a processed rotated log file through a well-known path, so that a sidecar can watch one path instead of the whole directory. Add it as the last processor to announce the final file.
+pub enum RotationMarker {
+    /// Replace the contents of a marker file with the path atomically (by renaming a temporary file).
+    Touch(PathBuf),
+    /// Write the path with a new line to a named pipe (FIFO) or to the end of a file. Nothing is written if no one is reading the named pipe.
+    Fifo(PathBuf),
+}
+
+impl RotationProcessor for RotationMarker {
+    fn process(&self, rotated_log_file: &Path) -> io::Result<PathBuf> {
+        let line = format!("{}\n", rotated_log_file.to_string_lossy());
+
+        match self {
+            RotationMarker::Touch(marker) => {
+                let mut temp = marker.as_os_str().to_os_string();
+                temp.push(".tmp");
+
+                fs::write(&temp, line)?;
+                fs::rename(&temp, marker)?;
+            },
+            RotationMarker::Fifo(fifo) => {
+                let mut options = OpenOptions::new();
+
+                options.append(true);
+
+                // do not block until a reader opens the named pipe
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::OpenOptionsExt;
+
+                    options.custom_flags(libc::O_NONBLOCK);
+                }
+
+                match options.open(fifo) {
+                    Ok(mut file) => file.write_all(line.as_bytes())?,
+                    #[cfg(unix)]
+                    Err(ref err) if err.raw_os_error() == Some(libc::ENXIO) => (),
+                    Err(err) => return Err(err),
+                }
+            },
+        }
+
+        Ok(rotated_log_file.to_path_buf())
+    }
+}
+
+#[derive(Debug)]
+/// Copy every processed rotated log file into a secondary directory (e.g. a mounted NAS), so that a copy exists as soon as the file is finalized. Add it as the last processor to mirror the final (compressed) file.
+///
+/// A file which fails to be mirrored is kept in a backlog instead of failing the processor chain, and the backlog is retried before every following file. The backlog holds at most `max_backlog` files; the oldest ones are dropped beyond that, and the ones removed by the retention are forgotten. Share the processor through `PipeLoggerBuilder::set_processors` to inspect the backlog.
+pub struct Mirror {
+    dir:         PathBuf,
+    max_backlog: usize,
+    backlog:     Mutex<VecDeque<PathBuf>>,
+    dropped:     Mutex<u64>,
+}
+
+impl Mirror {
+    /// Create a mirror into `dir`. The directory is not created, so that nothing is mirrored into the mount point if the secondary storage is not mounted.
+    #[inline]
+    pub fn new<P: Into<PathBuf>>(dir: P, max_backlog: usize) -> Mirror {
+        Mirror {
+            dir: dir.into(),
+            max_backlog,
+            backlog: Mutex::new(VecDeque::new()),
+            dropped: Mutex::new(0),
+        }
+    }
+
+    /// The secondary directory.
+    #[inline]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The files which have not been mirrored yet, from the oldest.
+    #[inline]
+    pub fn backlog(&self) -> Vec<PathBuf> {
+        self.backlog.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// The number of files dropped from the full backlog without being mirrored.
+    #[inline]
+    pub fn dropped_count(&self) -> u64 {
+        *self.dropped.lock().unwrap()
+    }
+
+    /// Copy a file into the directory through a temporary file, so that a partial copy never has the name of the file.
+    fn mirror(&self, file: &Path) -> io::Result<()> {
+        let file_name = match file.file_name() {
+            Some(file_name) => file_name.to_string_lossy(),
+            None => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "The file has no name."))
+            },
+        };
+
+        let mirrored = Path::join(&self.dir, file_name.as_ref());
+        let temp = Path::join(&self.dir, format!(".{}.mirroring", file_name));
+
+        let result = fs::copy(file, &temp)
+            .and_then(|_| File::open(&temp)?.sync_all())
+            .and_then(|_| fs::rename(&temp, &mirrored));
+
+        if result.is_err() && fs::remove_file(&temp).is_err() {
+            // do nothing
+        }
+
+        result
+    }
+}
+
+impl RotationProcessor for Mirror {
+    fn process(&self, rotated_log_file: &Path) -> io::Result<PathBuf> {
+        let mut backlog = self.backlog.lock().unwrap();
+
+        // the files in the backlog are retried in order, and the files which cannot be mirrored stay
+        let pending: Vec<PathBuf> = backlog.drain(..).collect();
+
+        for file in pending.into_iter().chain([rotated_log_file.to_path_buf()]) {
+            if backlog.contains(&file) || !file.exists() {
+                continue;
+            }
+
+            if self.mirror(&file).is_err() {
+                backlog.push_back(file);
+            }
+        }
+
+        while backlog.len() > self.max_backlog {
+            backlog.pop_front();
+
+            *self.dropped.lock().unwrap() += 1;
+        }
+
+        Ok(rotated_log_file.to_path_buf())
+    }
+}
@@ -0,0 +1,22 @@
+use std::{
+    env, io,
+    os::unix::{ffi::OsStrExt, net::UnixDatagram},
+};
+
+/// Send a state (e.g. `STATUS=...`) to the service manager through the `NOTIFY_SOCKET` datagram socket. It does nothing if the process is not run by systemd with notification enabled. Sockets in the abstract namespace are not supported.
+pub(crate) fn sd_notify(state: &str) -> io::Result<()> {
+    let socket_path = match env::var_os("NOTIFY_SOCKET") {
+        Some(socket_path) => socket_path,
+        None => return Ok(()),
+    };
+
+    if socket_path.as_bytes().first().map(|b| *b != b'/').unwrap_or(true) {
+        return Ok(());
+    }
+
+    let socket = UnixDatagram::unbound()?;
+
+    socket.send_to(state.as_bytes(), socket_path)?;
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The line formats for security events consumed by SIEM systems.
+pub enum SecurityEventFormat {
+    /// ArcSight Common Event Format (version 0).
+    Cef,
+    /// QRadar Log Event Extended Format (version 1.0).
+    Leef,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// The fields identifying the device (application) which produces security events.
+pub struct SecurityEventHeader {
+    pub vendor:  String,
+    pub product: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A security event.
+pub struct SecurityEvent {
+    /// The unique identifier of the type of this event.
+    pub signature_id: String,
+    /// A human-readable description of this event. It is not a part of the LEEF format.
+    pub name:         String,
+    /// The importance of this event, from 0 to 10.
+    pub severity:     u8,
+    /// Extra key-value pairs, such as `src`, `dst` and `msg`.
+    pub extensions:   Vec<(String, String)>,
+}
+
+impl SecurityEventHeader {
+    /// Format an event as one line (without a new line) in the given format.
+    pub fn format(&self, format: SecurityEventFormat, event: &SecurityEvent) -> String {
+        let mut line = String::new();
+
+        match format {
+            SecurityEventFormat::Cef => {
+                line.push_str("CEF:0|");
+
+                for field in
+                    [&self.vendor, &self.product, &self.version, &event.signature_id, &event.name]
+                {
+                    push_escaped_header_field(&mut line, field);
+                    line.push('|');
+                }
+
+                line.write_fmt(format_args!("{}|", event.severity.min(10))).unwrap();
+
+                for (i, (key, value)) in event.extensions.iter().enumerate() {
+                    if i > 0 {
+                        line.push(' ');
+                    }
+
+                    line.push_str(key);
+                    line.push('=');
+
+                    for c in value.chars() {
+                        match c {
+                            '\\' => line.push_str("\\\\"),
+                            '=' => line.push_str("\\="),
+                            '\n' => line.push_str("\\n"),
+                            '\r' => line.push_str("\\r"),
+                            c => line.push(c),
+                        }
+                    }
+                }
+            },
+            SecurityEventFormat::Leef => {
+                line.push_str("LEEF:1.0|");
+
+                for field in [&self.vendor, &self.product, &self.version, &event.signature_id] {
+                    push_escaped_header_field(&mut line, field);
+                    line.push('|');
+                }
+
+                line.write_fmt(format_args!("sev={}", event.severity.min(10))).unwrap();
+
+                for (key, value) in event.extensions.iter() {
+                    line.push('\t');
+                    line.push_str(key);
+                    line.push('=');
+
+                    // the tab character is the delimiter
+                    for c in value.chars() {
+                        match c {
+                            '\t' | '\n' | '\r' => line.push(' '),
+                            c => line.push(c),
+                        }
+                    }
+                }
+            },
+        }
+
+        line
+    }
+}
+
+#[inline]
+fn push_escaped_header_field(line: &mut String, field: &str) {
+    for c in field.chars() {
+        match c {
+            '\\' => line.push_str("\\\\"),
+            '|' => line.push_str("\\|"),
+            '\n' | '\r' => line.push(' '),
+            c => line.push(c),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use std::{io, path::PathBuf};
+
+#[derive(Debug)]
+/// What was left unfinished when a `PipeLogger` was closed.
+pub struct ShutdownReport {
+    /// The number of bytes in the active log file which are not confirmed to be durable because syncing failed.
+    pub unsynced_bytes:       u64,
+    /// The error occurred while syncing the active log file.
+    pub sync_error:           Option<io::Error>,
+    /// The compressed log files which were still being produced when the deadline was reached.
+    pub pending_compressions: Vec<PathBuf>,
+    /// The rotated log file produced by `set_rotate_on_close`.
+    pub rotated_log_file:     Option<PathBuf>,
+    /// The error occurred while writing the final marker or rotating the log file.
+    pub finalize_error:       Option<io::Error>,
+}
+
+impl ShutdownReport {
+    /// Whether everything was finished before the deadline.
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.unsynced_bytes == 0
+            && self.sync_error.is_none()
+            && self.pending_compressions.is_empty()
+            && self.finalize_error.is_none()
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, SyncSender, TrySendError},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{Tee, FILE_WAIT_MILLI_SECONDS};
+
+/// Writes the tee output in a background thread, so that a slow consumer of stdout/stderr does not block the logger.
+pub(crate) struct TeeBuffer {
+    capacity:    usize,
+    sender:      Option<SyncSender<Vec<u8>>>,
+    handle:      Option<JoinHandle<()>>,
+    error_count: Arc<AtomicU64>,
+}
+
+impl TeeBuffer {
+    /// Start a thread writing to `tee`, with room for `capacity` pending writes.
+    pub(crate) fn new(tee: Tee, capacity: usize) -> TeeBuffer {
+        let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(capacity.max(1));
+
+        let error_count = Arc::new(AtomicU64::new(0));
+
+        let thread_error_count = error_count.clone();
+
+        let handle = thread::spawn(move || {
+            for buf in receiver {
+                let result = match tee {
+                    Tee::Stdout => io::stdout().write_all(&buf),
+                    Tee::Stderr => io::stderr().write_all(&buf),
+                };
+
+                if result.is_err() {
+                    thread_error_count.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+
+        TeeBuffer {
+            capacity,
+            sender: Some(sender),
+            handle: Some(handle),
+            error_count,
+        }
+    }
+
+    /// The maximum number of pending writes.
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Queue some data without blocking. Returns `false` if the data is dropped because the buffer is full.
+    pub(crate) fn send(&self, buf: &[u8]) -> bool {
+        match &self.sender {
+            Some(sender) => match sender.try_send(buf.to_vec()) {
+                Ok(_) => true,
+                Err(TrySendError::Full(_)) => false,
+                // the thread has gone, count it as a failed write
+                Err(TrySendError::Disconnected(_)) => {
+                    self.error_count.fetch_add(1, Ordering::Relaxed);
+                    true
+                },
+            },
+            None => false,
+        }
+    }
+
+    /// The number of failed writes in the background thread.
+    pub(crate) fn error_count(&self) -> u64 {
+        self.error_count.load(Ordering::Relaxed)
+    }
+
+    /// Stop accepting data and wait for the queued data to be written until the deadline.
+    pub(crate) fn finish(&mut self, start: Instant, deadline: Duration) {
+        self.sender.take();
+
+        if let Some(handle) = self.handle.as_ref() {
+            while !handle.is_finished() && start.elapsed() < deadline {
+                thread::sleep(Duration::from_millis(FILE_WAIT_MILLI_SECONDS));
+            }
+
+            if handle.is_finished() {
+                self.handle.take().unwrap().join().ok();
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A retention which thins out the older rotated log files instead of removing them all, like the rotation schemes of backups. The ages of the files are counted from the timestamps in their names.
+///
+/// * The files younger than `keep_all` are all kept.
+/// * The files younger than `hourly` are kept one per hour.
+/// * The files younger than `daily` are kept one per day.
+/// * The older files are removed.
+///
+/// The oldest file of each hour or day (in UTC) is the one to be kept.
+pub struct Thinning {
+    /// Keep all of the files younger than this.
+    pub keep_all: Duration,
+    /// Keep one file per hour for the files younger than this.
+    pub hourly:   Duration,
+    /// Keep one file per day for the files younger than this.
+    pub daily:    Duration,
+}
+
+impl Default for Thinning {
+    /// Keep all of the files in the last hour, one per hour in the last day, and one per day in the last 30 days.
+    #[inline]
+    fn default() -> Self {
+        Thinning {
+            keep_all: Duration::from_secs(60 * 60),
+            hourly:   Duration::from_secs(24 * 60 * 60),
+            daily:    Duration::from_secs(30 * 24 * 60 * 60),
+        }
+    }
+}
+
+impl Thinning {
+    /// Select the rotated log files to be removed at `now`. The names need to be sorted from the oldest to the newest. The files whose timestamps cannot be parsed are kept.
+    pub(crate) fn select_removed(
+        &self,
+        rotated_log_file_names: &[String],
+        parse_timestamp: impl Fn(&str) -> Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Vec<String> {
+        let mut removed = Vec::new();
+
+        // (the tier, the number of the hour or day)
+        let mut last_bucket: Option<(u8, i64)> = None;
+
+        for rotated_log_file_name in rotated_log_file_names {
+            let timestamp = match parse_timestamp(rotated_log_file_name) {
+                Some(timestamp) => timestamp,
+                None => continue,
+            };
+
+            // a file from the future is as young as possible
+            let age = (now - timestamp).to_std().unwrap_or(Duration::ZERO);
+
+            let bucket = if age < self.keep_all {
+                continue;
+            } else if age < self.hourly {
+                (0, timestamp.timestamp().div_euclid(60 * 60))
+            } else if age < self.daily {
+                (1, timestamp.timestamp().div_euclid(24 * 60 * 60))
+            } else {
+                removed.push(rotated_log_file_name.clone());
+
+                continue;
+            };
+
+            if last_bucket == Some(bucket) {
+                removed.push(rotated_log_file_name.clone());
+            } else {
+                last_bucket = Some(bucket);
+            }
+        }
+
+        removed
+    }
+}
@@ -0,0 +1,61 @@
+use chrono::{DateTime, TimeZone, Utc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The precision of the timestamps in the names of rotated log files.
+pub enum TimestampPrecision {
+    /// `-%Y-%m-%d-%H-%M-%S`, such as `mylog-2024-01-02-03-04-05.txt`.
+    Seconds,
+    /// `-%Y-%m-%d-%H-%M-%S-%3f`, such as `mylog-2024-01-02-03-04-05-678.txt`.
+    Millis,
+    /// `-%Y-%m-%d-%H-%M-%S-%6f`, such as `mylog-2024-01-02-03-04-05-678901.txt`.
+    Micros,
+}
+
+impl Default for TimestampPrecision {
+    #[inline]
+    fn default() -> Self {
+        TimestampPrecision::Millis
+    }
+}
+
+impl TimestampPrecision {
+    /// The `chrono` format string of the timestamps, without the leading `-`.
+    #[inline]
+    pub(crate) fn format_str(&self) -> &'static str {
+        match self {
+            TimestampPrecision::Seconds => "%Y-%m-%d-%H-%M-%S",
+            TimestampPrecision::Millis => "%Y-%m-%d-%H-%M-%S-%3f",
+            TimestampPrecision::Micros => "%Y-%m-%d-%H-%M-%S-%6f",
+        }
+    }
+
+    /// The length of the timestamps formatted by `format_str`.
+    #[inline]
+    pub(crate) fn formatted_len(&self) -> usize {
+        match self {
+            TimestampPrecision::Seconds => 19,
+            TimestampPrecision::Millis => 23,
+            TimestampPrecision::Micros => 26,
+        }
+    }
+
+    /// The number of units of this precision since the Unix epoch.
+    #[inline]
+    pub(crate) fn tick(&self, utc: &DateTime<Utc>) -> i64 {
+        match self {
+            TimestampPrecision::Seconds => utc.timestamp(),
+            TimestampPrecision::Millis => utc.timestamp_millis(),
+            TimestampPrecision::Micros => utc.timestamp_micros(),
+        }
+    }
+
+    /// The inverse of `tick`.
+    #[inline]
+    pub(crate) fn datetime_of_tick(&self, tick: i64) -> DateTime<Utc> {
+        match self {
+            TimestampPrecision::Seconds => Utc.timestamp_opt(tick, 0).unwrap(),
+            TimestampPrecision::Millis => Utc.timestamp_millis_opt(tick).unwrap(),
+            TimestampPrecision::Micros => Utc.timestamp_nanos(tick * 1000),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use crate::{log_rates::RateCounter, Rate};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// When to raise a `VolumeAlert`.
+pub struct VolumeThreshold {
+    /// The maximum number of lines per second.
+    pub lines_per_second: Option<f64>,
+    /// The maximum number of bytes per second.
+    pub bytes_per_second: Option<f64>,
+    /// How long the rate has to stay above the threshold. It is counted in whole seconds, at least one.
+    pub sustained:        Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Raised when the write rate of a `PipeLogger` has stayed above a `VolumeThreshold`.
+pub struct VolumeAlert {
+    /// The rate during the last second.
+    pub rate:     Rate,
+    /// How long the rate has stayed above the threshold.
+    pub duration: Duration,
+}
+
+pub(crate) type VolumeAlertCallback = Box<dyn Fn(VolumeAlert) + Send>;
+
+/// Checks every second recorded by a `RateCounter` and calls the callback once each time the rate stays above the threshold for long enough.
+pub(crate) struct VolumeMonitor {
+    threshold: VolumeThreshold,
+    callback:  VolumeAlertCallback,
+    streak:    u64,
+    alerted:   bool,
+}
+
+impl VolumeMonitor {
+    pub(crate) fn new(threshold: VolumeThreshold, callback: VolumeAlertCallback) -> VolumeMonitor {
+        VolumeMonitor {
+            threshold,
+            callback,
+            streak: 0,
+            alerted: false,
+        }
+    }
+
+    /// Check `completed_second`, which has just been completed by the `rate_counter`.
+    pub(crate) fn check(&mut self, rate_counter: &RateCounter, completed_second: u64) {
+        let rate = rate_counter.second_rate(completed_second);
+
+        let exceeded = self.threshold.lines_per_second.map(|max| rate.lines > max).unwrap_or(false)
+            || self.threshold.bytes_per_second.map(|max| rate.bytes > max).unwrap_or(false);
+
+        if exceeded {
+            self.streak += 1;
+
+            if !self.alerted && self.streak >= self.threshold.sustained.as_secs().max(1) {
+                self.alerted = true;
+
+                (self.callback)(VolumeAlert {
+                    rate,
+                    duration: Duration::from_secs(self.streak),
+                });
+            }
+        } else {
+            self.streak = 0;
+            self.alerted = false;
+        }
+
+        // nothing was written in the seconds between, so the rate was below the threshold
+        if rate_counter.second() > completed_second + 1 {
+            self.streak = 0;
+            self.alerted = false;
+        }
+    }
+}
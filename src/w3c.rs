@@ -0,0 +1,53 @@
+use std::io::{self, Write};
+
+use chrono::{DateTime, Utc};
+
+/// Write the directives of the W3C extended log format, which a new log file begins with. Returns the number of written bytes.
+pub(crate) fn write_w3c_header<W: Write>(
+    writer: &mut W,
+    fields: &[String],
+    date: DateTime<Utc>,
+) -> io::Result<u64> {
+    let header = format!(
+        "#Version: 1.0\n#Fields: {}\n#Date: {}\n",
+        fields.join(" "),
+        date.format("%Y-%m-%d %H:%M:%S")
+    );
+
+    writer.write_all(header.as_bytes())?;
+
+    Ok(header.len() as u64)
+}
+
+/// Format values as an entry of the W3C extended log format, without a new line. An empty value is written as `-`, and a value containing whitespace or `"` is quoted.
+pub(crate) fn format_w3c_entry<S: AsRef<str>>(values: &[S]) -> String {
+    let mut entry = String::new();
+
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            entry.push(' ');
+        }
+
+        let value = value.as_ref();
+
+        if value.is_empty() {
+            entry.push('-');
+        } else if value.contains(|c: char| c.is_whitespace() || c == '"') {
+            entry.push('"');
+
+            for c in value.chars() {
+                match c {
+                    '"' => entry.push_str("\"\""),
+                    '\r' | '\n' => entry.push(' '),
+                    c => entry.push(c),
+                }
+            }
+
+            entry.push('"');
+        } else {
+            entry.push_str(value);
+        }
+    }
+
+    entry
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "capi")]
+
+use std::{ffi::CString, fs, path::Path, ptr};
+
+use pipe_logger_lib::pipe_logger_capi::*;
+
+#[test]
+fn write_rotate_close() {
+    let test_folder = Path::join(&Path::join(Path::new("tests"), Path::new("out")), "capi");
+
+    fs::create_dir_all(&test_folder).unwrap();
+
+    let log_path = CString::new(Path::join(&test_folder, "logfile.log").to_str().unwrap()).unwrap();
+
+    unsafe {
+        assert!(pipe_logger_new(ptr::null(), 0, 0, 0).is_null());
+
+        let logger = pipe_logger_new(log_path.as_ptr(), 10, 0, 1);
+
+        assert!(!logger.is_null());
+
+        let line = CString::new("Hello world!").unwrap();
+
+        assert_eq!(1, pipe_logger_write_line(logger, line.as_ptr()));
+        assert_eq!(-1, pipe_logger_write_line(logger, ptr::null()));
+
+        assert_eq!(0, pipe_logger_rotate(logger));
+
+        let line = CString::new("Hi").unwrap();
+
+        assert_eq!(0, pipe_logger_write_line(logger, line.as_ptr()));
+        assert_eq!(1, pipe_logger_rotate(logger));
+
+        assert_eq!(0, pipe_logger_close(logger, 10000));
+    }
+
+    let compressed_count = test_folder
+        .read_dir()
+        .unwrap()
+        .filter(|entry| entry.as_ref().unwrap().path().extension().unwrap() == "xz")
+        .count();
+
+    assert_eq!(2, compressed_count);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn error_paths() {
+    let test_folder = Path::join(&Path::join(Path::new("tests"), Path::new("out")), "capi-errors");
+
+    fs::create_dir_all(&test_folder).unwrap();
+
+    let folder_path = CString::new(test_folder.to_str().unwrap()).unwrap();
+    let log_path = Path::join(&test_folder, "logfile.log");
+    let log_path_c = CString::new(log_path.to_str().unwrap()).unwrap();
+
+    let line = CString::new("Hello world!").unwrap();
+
+    unsafe {
+        // a directory is not a log file
+        assert!(pipe_logger_new(folder_path.as_ptr(), 0, 0, 0).is_null());
+
+        assert_eq!(-1, pipe_logger_write_line(ptr::null_mut(), line.as_ptr()));
+        assert_eq!(-1, pipe_logger_rotate(ptr::null_mut()));
+        assert_eq!(-1, pipe_logger_close(ptr::null_mut(), 0));
+
+        let logger = pipe_logger_new(log_path_c.as_ptr(), 0, 0, 0);
+
+        assert!(!logger.is_null());
+
+        assert_eq!(0, pipe_logger_write_line(logger, line.as_ptr()));
+
+        // the log file to be renamed is gone
+        #[cfg(unix)]
+        {
+            fs::remove_file(&log_path).unwrap();
+
+            assert_eq!(-1, pipe_logger_rotate(logger));
+
+            // the logger keeps working after the failed rotation
+            assert_eq!(0, pipe_logger_write_line(logger, line.as_ptr()));
+        }
+
+        assert_eq!(0, pipe_logger_close(logger, 10000));
+    }
+
+    #[cfg(unix)]
+    assert_eq!("Hello world!\n", fs::read_to_string(&log_path).unwrap());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
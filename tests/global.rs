@@ -0,0 +1,44 @@
+use std::{fs, path::Path};
+
+use pipe_logger_lib::*;
+
+const LOG_FILE_NAME: &str = "logfile.log";
+
+#[test]
+fn init_global() {
+    let test_folder = Path::join(&Path::join(Path::new("tests"), Path::new("out")), "global");
+
+    fs::create_dir_all(&test_folder).unwrap();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    assert!(global().is_none());
+
+    init(PipeLoggerBuilder::new(&test_log_path)).unwrap();
+
+    assert!(matches!(
+        init(PipeLoggerBuilder::new(&test_log_path)),
+        Err(PipeLoggerBuilderError::GlobalLoggerAlreadyInitialized)
+    ));
+
+    global().unwrap().lock().unwrap().write_line("This is a log.").unwrap();
+
+    let string = fs::read_to_string(&test_log_path).unwrap();
+
+    assert_eq!("This is a log.\n", string);
+
+    // the global logger is also the logger of the `log` crate
+    #[cfg(feature = "log")]
+    {
+        log::info!(target: "app", "This is a record.");
+        log::debug!(target: "app", "This is ignored.");
+
+        let string = fs::read_to_string(&test_log_path).unwrap();
+
+        assert!(string.starts_with("This is a log.\n"));
+        assert!(string.ends_with(" [INFO] app: This is a record.\n"));
+        assert_eq!(2, string.lines().count());
+    }
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
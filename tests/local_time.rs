@@ -0,0 +1,59 @@
+#![cfg(unix)]
+
+use std::{
+    env, fs,
+    path::Path,
+    sync::atomic::{AtomicI64, Ordering},
+};
+
+use pipe_logger_lib::*;
+
+static MANUAL_CLOCK_SECONDS: AtomicI64 = AtomicI64::new(946684800);
+
+fn manual_clock() -> chrono::DateTime<chrono::Utc> {
+    chrono::TimeZone::timestamp_opt(&chrono::Utc, MANUAL_CLOCK_SECONDS.load(Ordering::SeqCst), 0)
+        .unwrap()
+}
+
+#[test]
+fn write_rotate_daily_in_local_time() {
+    let test_folder = Path::join(&Path::join(Path::new("tests"), Path::new("out")), "local_time");
+
+    fs::create_dir_all(&test_folder).unwrap();
+
+    // UTC+05:30
+    env::set_var("TZ", "XST-5:30");
+
+    let test_log_path = Path::join(&test_folder, "logfile.log");
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotate(Some(RotateMethod::Daily));
+    builder.set_local_time(true);
+    builder.set_clock(manual_clock);
+
+    // 2000-01-01 23:59:59 in local time
+    MANUAL_CLOCK_SECONDS.store(946684800 + 18 * 3600 + 30 * 60 - 1, Ordering::SeqCst);
+
+    let mut logger = builder.build().unwrap();
+
+    assert!(logger.write_line("Log 1").unwrap().is_none());
+
+    // 2000-01-02 00:00:00 in local time, which is still 2000-01-01 in UTC
+    MANUAL_CLOCK_SECONDS.fetch_add(1, Ordering::SeqCst);
+
+    let new_file = logger.write_line("Log 2").unwrap().unwrap();
+
+    assert_eq!(
+        "logfile-2000-01-02-00-00-00-000.log",
+        new_file.file_name().unwrap().to_str().unwrap()
+    );
+    assert_eq!("Log 1\n", fs::read_to_string(new_file).unwrap());
+
+    // 2000-01-02 23:59:59 in local time
+    MANUAL_CLOCK_SECONDS.fetch_add(24 * 3600 - 1, Ordering::SeqCst);
+
+    assert!(logger.write_line("Log 3").unwrap().is_none());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
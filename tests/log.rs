@@ -0,0 +1,55 @@
+#![cfg(feature = "log")]
+
+use std::{fs, path::Path};
+
+use chrono::{DateTime, TimeZone, Utc};
+use log::LevelFilter;
+use pipe_logger_lib::*;
+
+const LOG_FILE_NAME: &str = "logfile.log";
+
+fn fixed_clock() -> DateTime<Utc> {
+    Utc.timestamp_opt(1_600_000_000, 0).unwrap()
+}
+
+#[test]
+fn init_log_backend() {
+    let test_folder = Path::join(&Path::join(Path::new("tests"), Path::new("out")), "log");
+
+    fs::create_dir_all(&test_folder).unwrap();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_clock(fixed_clock);
+
+    let backend = PipeLoggerBackend::new(builder.build().unwrap(), LevelFilter::Info);
+
+    assert_eq!(DEFAULT_RECORD_FORMAT, backend.format());
+
+    backend.with_format("{timestamp} {level} {target} {unknown}: {message}").init().unwrap();
+
+    assert!(matches!(
+        PipeLoggerBuilder::new(&test_log_path).init_log(LevelFilter::Trace),
+        Err(PipeLoggerBuilderError::GlobalLoggerAlreadyInitialized)
+    ));
+
+    log::info!(target: "app", "This is a log.");
+    log::debug!(target: "app", "This is ignored.");
+    log::warn!(target: "app::db", "This is a {}.", "warning");
+    log::logger().flush();
+
+    // the backend writes through the global logger
+    global().unwrap().lock().unwrap().write_line("This is not a record.").unwrap();
+
+    let string = fs::read_to_string(&test_log_path).unwrap();
+
+    assert_eq!(
+        "2020-09-13T12:26:40.000Z INFO app {unknown}: This is a log.\n2020-09-13T12:26:40.000Z \
+         WARN app::db {unknown}: This is a warning.\nThis is not a record.\n",
+        string
+    );
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "log")]
+
+use std::{fs, path::Path};
+
+use log::{LevelFilter, Log, Metadata, Record};
+use pipe_logger_lib::*;
+
+const LOG_FILE_NAME: &str = "logfile.log";
+
+/// A logger of the `log` crate installed by someone else.
+struct ForeignLogger;
+
+impl Log for ForeignLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, _record: &Record) {}
+
+    fn flush(&self) {}
+}
+
+static FOREIGN_LOGGER: ForeignLogger = ForeignLogger;
+
+#[test]
+fn init_after_foreign_log_logger() {
+    let test_folder = Path::join(&Path::join(Path::new("tests"), Path::new("out")), "log_foreign");
+
+    fs::create_dir_all(&test_folder).unwrap();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    log::set_logger(&FOREIGN_LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Info);
+
+    assert!(matches!(
+        init(PipeLoggerBuilder::new(&test_log_path)),
+        Err(PipeLoggerBuilderError::LogLoggerAlreadyInitialized)
+    ));
+
+    // the global logger is still installed
+    global().unwrap().lock().unwrap().write_line("This is a log.").unwrap();
+
+    log::info!(target: "app", "This is not written.");
+
+    let string = fs::read_to_string(&test_log_path).unwrap();
+
+    assert_eq!("This is a log.\n", string);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
@@ -1,7 +1,12 @@
 use std::{
+    collections::BTreeMap,
     fs,
+    io::{self, Read},
     path::{Path, PathBuf},
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::{
+        atomic::{AtomicI64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
     thread,
     time::Duration,
 };
@@ -11,11 +16,10 @@ use pipe_logger_lib::*;
 const LOG_FILE_NAME: &str = "logfile.log";
 const WAIT_DURATION_MILLI_SECONDS: u64 = 1000;
 
-static mut LAST_TEST_FOLDER_TIME: AtomicUsize = AtomicUsize::new(0);
+static LAST_TEST_FOLDER_TIME: AtomicUsize = AtomicUsize::new(0);
 
 fn create_test_folder() -> PathBuf {
-    let test_folder_name =
-        { unsafe { LAST_TEST_FOLDER_TIME.fetch_add(1, Ordering::SeqCst) }.to_string() };
+    let test_folder_name = LAST_TEST_FOLDER_TIME.fetch_add(1, Ordering::SeqCst).to_string();
 
     let folder =
         Path::join(&Path::join(Path::new("tests"), Path::new("out")), Path::new(&test_folder_name));
@@ -44,6 +48,56 @@ fn build() {
     fs::remove_dir_all(test_folder).unwrap();
 }
 
+#[test]
+fn builder_eq() {
+    let mut builder = PipeLoggerBuilder::<PathBuf>::default();
+
+    assert_eq!(PathBuf::new(), *builder.log_path());
+
+    builder.set_rotate(Some(RotateMethod::FileSize(24)));
+    builder.set_count(Some(5));
+    builder.set_tee(Some(Tee::Stderr));
+    builder.set_retention_exemption(Some(regex::Regex::new("pinned").unwrap()));
+    builder.add_processor(SlowProcessor);
+
+    let cloned = builder.clone();
+
+    assert_eq!(builder, cloned);
+    assert_eq!(Some(RotateMethod::FileSize(24)), cloned.rotate());
+    assert_eq!(Some(5), cloned.count());
+    assert_eq!(Some(Tee::Stderr), cloned.tee());
+
+    builder.add_processor(SlowProcessor);
+
+    assert_ne!(builder, cloned);
+}
+
+#[test]
+fn builder_into_owned() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let builder = {
+        let test_log_path = test_log_path.to_str().unwrap().to_string();
+
+        let mut builder = PipeLoggerBuilder::new(test_log_path.as_str());
+
+        builder.set_count(Some(5));
+
+        builder.into_owned()
+    };
+
+    assert_eq!(test_log_path, *builder.log_path());
+    assert_eq!(Some(5), builder.count());
+
+    thread::spawn(move || builder.build().unwrap()).join().unwrap();
+
+    assert!(test_log_path.is_file());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
 #[test]
 fn write() {
     let test_folder = create_test_folder();
@@ -157,65 +211,193 @@ fn write_tee_err() {
 }
 
 #[test]
-fn write_rotate() {
+fn write_tee_buffer() {
     let test_folder = create_test_folder();
 
     let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
 
-    let new_file = {
+    {
         let mut builder = PipeLoggerBuilder::new(&test_log_path);
 
-        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_tee(Some(Tee::Stderr));
+        builder.set_tee_buffer(Some(16));
 
         let mut logger = builder.build().unwrap();
 
         logger.write_line("This is a log.").unwrap();
-        let new_file = logger.write_line("Isn't it?").unwrap().unwrap();
+        logger.write_line("Isn't it?").unwrap();
 
-        logger.write_line("New file!!!!").unwrap();
+        assert_eq!(0, logger.tee_dropped_count());
 
-        new_file
-    };
+        assert!(logger.close(Duration::from_secs(10)).is_complete());
+    }
 
-    let string_1 = fs::read_to_string(test_log_path).unwrap();
+    let string = fs::read_to_string(test_log_path).unwrap();
 
-    assert_eq!("New file!!!!\n", string_1);
+    assert_eq!("This is a log.\nIsn't it?\n", string);
 
-    let string_2 = fs::read_to_string(new_file).unwrap();
+    fs::remove_dir_all(test_folder).unwrap();
+}
 
-    assert_eq!("This is a log.\nIsn't it?", string_2);
+#[derive(Clone)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
 
-    fs::remove_dir_all(test_folder).unwrap();
+impl io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct BrokenSink;
+
+impl io::Write for BrokenSink {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 #[test]
-fn write_rotate_with_count() {
+fn write_extra_sink() {
     let test_folder = create_test_folder();
 
     let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
 
-    let new_file = {
+    let buffer = SharedBuffer(Arc::new(Mutex::new(Vec::new())));
+
+    {
         let mut builder = PipeLoggerBuilder::new(&test_log_path);
 
-        builder.set_rotate(Some(RotateMethod::FileSize(24)));
-        builder.set_count(Some(5));
+        builder.set_tee(Some(Tee::Stderr));
+        builder.set_tee_buffer(Some(16));
+        builder.set_extra_sink(Some(Box::new(buffer.clone())));
+
+        assert!(builder.has_extra_sink());
 
         let mut logger = builder.build().unwrap();
 
         logger.write_line("This is a log.").unwrap();
-        logger.write_line("Isn't it?").unwrap().unwrap();
+        logger.write_line("Isn't it?").unwrap();
 
-        logger.write_line("This is a log.").unwrap();
-        logger.write_line("Isn't it?").unwrap().unwrap();
+        assert_eq!(0, logger.tee_error_count());
+        assert_eq!(0, logger.extra_sink_error_count());
+    }
 
-        logger.write_line("This is a log.").unwrap();
-        logger.write_line("Isn't it?").unwrap().unwrap();
+    assert_eq!(b"This is a log.\nIsn't it?\n".as_slice(), buffer.0.lock().unwrap().as_slice());
+
+    let string = fs::read_to_string(&test_log_path).unwrap();
+
+    assert_eq!("This is a log.\nIsn't it?\n", string);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_extra_sink_error() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let errors = Arc::new(Mutex::new(Vec::new()));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_extra_sink(Some(Box::new(BrokenSink)));
+
+        let mut logger = builder.build().unwrap();
+
+        let callback_errors = errors.clone();
+
+        logger.set_sink_error_callback(Box::new(move |err| {
+            callback_errors.lock().unwrap().push(err.kind());
+        }));
 
         logger.write_line("This is a log.").unwrap();
-        logger.write_line("Isn't it?").unwrap().unwrap();
+        logger.write_line("Isn't it?").unwrap();
+
+        assert_eq!(2, logger.extra_sink_error_count());
+        assert_eq!(0, logger.tee_error_count());
+    }
+
+    assert_eq!(vec![io::ErrorKind::BrokenPipe; 2], *errors.lock().unwrap());
+
+    let string = fs::read_to_string(&test_log_path).unwrap();
+
+    assert_eq!("This is a log.\nIsn't it?\n", string);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+/// A broken sink which records the content of the log file when it is written.
+struct ObservingSink {
+    log_path: PathBuf,
+    observed: Arc<Mutex<Vec<String>>>,
+}
+
+impl io::Write for ObservingSink {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        self.observed.lock().unwrap().push(fs::read_to_string(&self.log_path).unwrap());
+
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn write_extra_sink_after_file() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let observed = Arc::new(Mutex::new(Vec::new()));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_extra_sink(Some(Box::new(ObservingSink {
+            log_path: test_log_path.clone(),
+            observed: observed.clone(),
+        })));
+
+        let mut logger = builder.build().unwrap();
 
         logger.write_line("This is a log.").unwrap();
-        logger.write_line("Isn't it?").unwrap().unwrap();
+
+        assert_eq!(1, logger.extra_sink_error_count());
+    }
+
+    // the line is in the log file before the sink fails
+    assert_eq!(vec!["This is a log.\n".to_string()], *observed.lock().unwrap());
+    assert_eq!("This is a log.\n", fs::read_to_string(&test_log_path).unwrap());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let new_file = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+
+        let mut logger = builder.build().unwrap();
 
         logger.write_line("This is a log.").unwrap();
         let new_file = logger.write_line("Isn't it?").unwrap().unwrap();
@@ -225,8 +407,6 @@ fn write_rotate_with_count() {
         new_file
     };
 
-    assert_eq!(5, test_folder.read_dir().unwrap().count());
-
     let string_1 = fs::read_to_string(test_log_path).unwrap();
 
     assert_eq!("New file!!!!\n", string_1);
@@ -239,114 +419,3123 @@ fn write_rotate_with_count() {
 }
 
 #[test]
-fn write_rotate_with_compress() {
+fn write_rotate_tee() {
     let test_folder = create_test_folder();
 
     let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
 
-    let mut new_files = Vec::new();
+    let buffer = SharedBuffer(Arc::new(Mutex::new(Vec::new())));
 
     {
         let mut builder = PipeLoggerBuilder::new(&test_log_path);
 
         builder.set_rotate(Some(RotateMethod::FileSize(24)));
-        builder.set_compress(true);
+        builder.set_tee(Some(Tee::Stderr));
+        builder.set_extra_sink(Some(Box::new(buffer.clone())));
 
         let mut logger = builder.build().unwrap();
 
         logger.write_line("This is a log.").unwrap();
-        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+        logger.write_line("Isn't it?").unwrap().unwrap();
+        logger.write_line("New file!!!!").unwrap();
 
-        logger.write_line("This is a log.").unwrap();
-        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+        assert_eq!(0, logger.tee_error_count());
+    }
 
-        logger.write_line("This is a log.").unwrap();
-        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+    // the new line left out of the rotated log file is still written to the tee output
+    assert_eq!(
+        b"This is a log.\nIsn't it?\nNew file!!!!\n".as_slice(),
+        buffer.0.lock().unwrap().as_slice()
+    );
 
-        logger.write_line("This is a log.").unwrap();
-        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+    fs::remove_dir_all(test_folder).unwrap();
+}
 
-        logger.write_line("This is a log.").unwrap();
-        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+#[test]
+fn write_rotate_with_verify_reopen() {
+    let test_folder = create_test_folder();
 
-        logger.write_line("This is a log.").unwrap();
-        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
 
-        logger.write_line("New file!!!!").unwrap();
-    };
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
 
-    thread::sleep(Duration::from_millis(WAIT_DURATION_MILLI_SECONDS));
+    builder.set_rotate(Some(RotateMethod::FileSize(24)));
+    builder.set_verify_reopen(true);
 
-    if test_folder.read_dir().unwrap().count() != 7 {
-        thread::sleep(Duration::from_millis(WAIT_DURATION_MILLI_SECONDS * 2));
-        if test_folder.read_dir().unwrap().count() != 7 {
-            thread::sleep(Duration::from_millis(WAIT_DURATION_MILLI_SECONDS * 3));
-            assert_eq!(7, test_folder.read_dir().unwrap().count());
-        }
-    }
+    let mut logger = builder.build().unwrap();
 
-    for new_file in new_files {
-        assert!(new_file.exists());
+    let mismatches = Arc::new(AtomicUsize::new(0));
+    let mismatches_2 = mismatches.clone();
 
-        assert!(new_file.to_str().unwrap().ends_with(".xz"));
-    }
+    logger.set_reopen_mismatch_callback(Box::new(move |_| {
+        mismatches_2.fetch_add(1, Ordering::SeqCst);
+    }));
+
+    logger.write_line("This is a log.").unwrap();
+    let new_file = logger.write_line("Isn't it?").unwrap().unwrap();
+
+    // another writer appends to the log file, which is not overwritten by the logger
+    std::io::Write::write_all(
+        &mut fs::OpenOptions::new().append(true).open(&test_log_path).unwrap(),
+        b"Another writer\n",
+    )
+    .unwrap();
+
+    logger.write_line("New file!!!!").unwrap();
+
+    assert_eq!(0, mismatches.load(Ordering::SeqCst));
+    assert_eq!("This is a log.\nIsn't it?", fs::read_to_string(new_file).unwrap());
+    assert_eq!("Another writer\nNew file!!!!\n", fs::read_to_string(&test_log_path).unwrap());
 
     fs::remove_dir_all(test_folder).unwrap();
 }
 
+static MANUAL_CLOCK_SECONDS: AtomicI64 = AtomicI64::new(946684800);
+
+fn manual_clock() -> chrono::DateTime<chrono::Utc> {
+    chrono::TimeZone::timestamp_opt(&chrono::Utc, MANUAL_CLOCK_SECONDS.load(Ordering::SeqCst), 0)
+        .unwrap()
+}
+
 #[test]
-fn write_rotate_with_count_compress() {
+fn write_rotate_by_time() {
     let test_folder = create_test_folder();
 
     let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
 
-    let mut new_files = Vec::new();
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
 
-    {
+    builder.set_rotate(Some(RotateMethod::FileSizeOrHourly(1024)));
+    builder.set_clock(manual_clock);
+
+    // 2000-01-01 00:59:59
+    MANUAL_CLOCK_SECONDS.store(946684800 + 3599, Ordering::SeqCst);
+
+    let mut logger = builder.build().unwrap();
+
+    assert!(logger.write_line("Log 1").unwrap().is_none());
+
+    // 2000-01-01 01:00:00
+    MANUAL_CLOCK_SECONDS.fetch_add(1, Ordering::SeqCst);
+
+    // the data written after the hour is in the new log file
+    let new_file = logger.write_line("Log 2").unwrap().unwrap();
+
+    assert_eq!(
+        "logfile-2000-01-01-01-00-00-000.log",
+        new_file.file_name().unwrap().to_str().unwrap()
+    );
+    assert_eq!("Log 1\n", fs::read_to_string(new_file).unwrap());
+    assert_eq!("Log 2\n", fs::read_to_string(&test_log_path).unwrap());
+
+    // 2000-01-01 01:59:59
+    MANUAL_CLOCK_SECONDS.fetch_add(3599, Ordering::SeqCst);
+
+    assert!(logger.write_line("Log 3").unwrap().is_none());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_min_file_age() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let new_file = {
         let mut builder = PipeLoggerBuilder::new(&test_log_path);
 
         builder.set_rotate(Some(RotateMethod::FileSize(24)));
-        builder.set_count(Some(5));
-        builder.set_compress(true);
+        builder.set_min_file_age_before_rotation(Duration::from_millis(500));
 
         let mut logger = builder.build().unwrap();
 
         logger.write_line("This is a log.").unwrap();
-        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+        assert!(logger.write_line("Isn't it?").unwrap().is_none());
 
-        logger.write_line("This is a log.").unwrap();
-        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+        thread::sleep(Duration::from_millis(500));
 
-        logger.write_line("This is a log.").unwrap();
-        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+        logger.write_line("This is a log.").unwrap().unwrap()
+    };
 
-        logger.write_line("This is a log.").unwrap();
-        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+    let string = fs::read_to_string(new_file).unwrap();
 
-        logger.write_line("This is a log.").unwrap();
-        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+    assert_eq!("This is a log.\nIsn't it?\nThis is a log.", string);
 
-        logger.write_line("This is a log.").unwrap();
-        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+    fs::remove_dir_all(test_folder).unwrap();
+}
 
-        logger.write_line("New file!!!!").unwrap();
-    };
+#[test]
+fn write_rotate_with_sequence() {
+    let test_folder = create_test_folder();
 
-    thread::sleep(Duration::from_millis(WAIT_DURATION_MILLI_SECONDS));
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let mut new_files = Vec::new();
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(2)));
+        builder.set_rotation_sequence(true);
+
+        let mut logger = builder.build().unwrap();
 
-    if test_folder.read_dir().unwrap().count() != 5 {
-        thread::sleep(Duration::from_millis(WAIT_DURATION_MILLI_SECONDS * 2));
-        if test_folder.read_dir().unwrap().count() != 5 {
-            thread::sleep(Duration::from_millis(WAIT_DURATION_MILLI_SECONDS * 3));
-            assert_eq!(5, test_folder.read_dir().unwrap().count());
+        for _ in 0..5 {
+            new_files.push(logger.write_line("Log").unwrap().unwrap());
         }
     }
 
-    for new_file in new_files.iter().skip(2) {
-        assert!(new_file.exists());
+    let mut sorted_files = new_files.clone();
+    sorted_files.sort();
+    sorted_files.dedup();
 
-        assert!(new_file.to_str().unwrap().ends_with(".xz"));
+    assert_eq!(new_files, sorted_files);
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(2)));
+        builder.set_count(Some(2));
+        builder.set_rotation_sequence(true);
+
+        let mut logger = builder.build().unwrap();
+
+        new_files.push(logger.write_line("Log").unwrap().unwrap());
     }
 
+    assert_eq!(2, test_folder.read_dir().unwrap().count());
+    assert!(new_files[5].exists());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_timestamp_precision() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let mut new_files = Vec::new();
+
+    for (precision, len) in [
+        (TimestampPrecision::Seconds, 31),
+        (TimestampPrecision::Seconds, 31),
+        (TimestampPrecision::Micros, 38),
+        (TimestampPrecision::Millis, 35),
+    ] {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(2)));
+        builder.set_timestamp_precision(precision);
+
+        let mut logger = builder.build().unwrap();
+
+        let new_file = logger.write_line("Log").unwrap().unwrap();
+
+        assert_eq!(len, new_file.file_name().unwrap().len());
+
+        new_files.push(new_file);
+    }
+
+    assert_ne!(new_files[0], new_files[1]);
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(2)));
+        builder.set_count(Some(3));
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("Log").unwrap().unwrap();
+    }
+
+    // the oldest ones are removed
+    assert!(!new_files[0].exists());
+    assert!(!new_files[1].exists());
+    assert!(!new_files[2].exists());
+    assert!(new_files[3].exists());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_name_split() {
+    let test_folder = create_test_folder();
+
+    let cases = [
+        (".env.log", NameSplit::LastDot, ".env-", ".log"),
+        (".env", NameSplit::LastDot, ".env-", ""),
+        ("logfile", NameSplit::LastDot, "logfile-", ""),
+        ("logfile.tar.log", NameSplit::FirstDot, "logfile-", ".tar.log"),
+        ("logfile.tar.log", NameSplit::NoExtension, "logfile.tar.log-", ""),
+        (
+            "logfile.tar.log",
+            NameSplit::Explicit {
+                stem: "logfile.t".to_string(), ext: "ar.log".to_string()
+            },
+            "logfile.t-",
+            "ar.log",
+        ),
+    ];
+
+    for (file_name, name_split, prefix, suffix) in cases {
+        let test_log_path = Path::join(&test_folder, Path::new(file_name));
+
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(2)));
+        builder.set_name_split(name_split.clone());
+
+        let mut logger = builder.build().unwrap();
+
+        let new_file = logger.write_line("Log").unwrap().unwrap();
+
+        let new_file_name = new_file.file_name().unwrap().to_str().unwrap();
+
+        assert!(new_file_name.starts_with(prefix), "{}", new_file_name);
+        assert!(new_file_name.ends_with(suffix), "{}", new_file_name);
+
+        let searcher = PipeLogSearcher::with_name_split(&test_log_path, &name_split).unwrap();
+
+        assert_eq!(1, searcher.grep("Log", None).unwrap().count());
+    }
+
+    let mut builder = PipeLoggerBuilder::new(Path::join(&test_folder, Path::new("logfile.log")));
+
+    builder
+        .set_name_split(NameSplit::Explicit {
+            stem: "log".to_string(), ext: ".log".to_string()
+        });
+
+    assert!(matches!(builder.build(), Err(PipeLoggerBuilderError::IOError(_))));
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[derive(Debug)]
+struct SlowProcessor;
+
+impl RotationProcessor for SlowProcessor {
+    fn process(&self, rotated_log_file: &Path) -> std::io::Result<PathBuf> {
+        thread::sleep(Duration::from_millis(WAIT_DURATION_MILLI_SECONDS));
+
+        Ok(rotated_log_file.to_path_buf())
+    }
+}
+
+#[test]
+fn write_rotate_with_backlog_growth() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_max_backlog_growth(4);
+        builder.add_processor(SlowProcessor);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Isn't it?").unwrap().unwrap();
+
+        // one rotated log file is still being processed, so the rotation size is doubled
+        logger.write_line("This is a log.").unwrap();
+        assert!(logger.write_line("Isn't it?").unwrap().is_none());
+
+        logger.write_line("This is a log.").unwrap();
+        assert!(logger.write_line("Isn't it?").unwrap().is_some());
+
+        logger.close(Duration::from_secs(10));
+    }
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+fn fixed_clock() -> chrono::DateTime<chrono::Utc> {
+    chrono::TimeZone::timestamp_opt(&chrono::Utc, 946684800, 0).unwrap()
+}
+
+#[test]
+fn write_rotate_deterministic() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotate(Some(RotateMethod::FileSize(2)));
+    builder.set_compress(true);
+    builder.set_deterministic(true);
+    builder.set_clock(fixed_clock);
+
+    let mut logger = builder.build().unwrap();
+
+    let new_file_1 = logger.write_line("Log").unwrap().unwrap();
+    let new_file_2 = logger.write_line("Log").unwrap().unwrap();
+
+    // compressed before `write_line` returns
+    assert_eq!(3, test_folder.read_dir().unwrap().count());
+    assert!(new_file_1.exists());
+    assert!(new_file_2.exists());
+
+    assert_eq!(
+        "logfile-2000-01-01-00-00-00-000.log.xz",
+        new_file_1.file_name().unwrap().to_str().unwrap()
+    );
+    assert_eq!(
+        "logfile-2000-01-01-00-00-00-001.log.xz",
+        new_file_2.file_name().unwrap().to_str().unwrap()
+    );
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+fn unsynced_clock() -> chrono::DateTime<chrono::Utc> {
+    chrono::TimeZone::timestamp_opt(&chrono::Utc, 0, 0).unwrap()
+}
+
+#[test]
+fn write_rotate_with_min_valid_time() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    fs::write(Path::join(&test_folder, "logfile-2000-01-01-00-00-00-000.log"), "Old\n").unwrap();
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotate(Some(RotateMethod::FileSize(2)));
+    builder.set_clock(unsynced_clock);
+    builder.set_min_valid_time(Some(fixed_clock() - chrono::Duration::days(365)));
+
+    let mut logger = builder.clone().build().unwrap();
+
+    let new_file_1 = logger.write_line("Log").unwrap().unwrap();
+    let new_file_2 = logger.write_line("Log").unwrap().unwrap();
+
+    // ordered after the existing rotated log file rather than in 1970
+    assert_eq!(
+        "logfile-2000-01-01-00-00-00-001-000.log",
+        new_file_1.file_name().unwrap().to_str().unwrap()
+    );
+    assert_eq!(
+        "logfile-2000-01-01-00-00-00-001-001.log",
+        new_file_2.file_name().unwrap().to_str().unwrap()
+    );
+
+    drop(logger);
+
+    // the sequence continues after a restart
+    let mut logger = builder.build().unwrap();
+
+    let new_file_3 = logger.write_line("Log").unwrap().unwrap();
+
+    assert_eq!(
+        "logfile-2000-01-01-00-00-00-001-002.log",
+        new_file_3.file_name().unwrap().to_str().unwrap()
+    );
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_min_valid_time_in_seconds() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    fs::write(Path::join(&test_folder, "logfile-2000-01-01-00-00-00-000.log"), "Old\n").unwrap();
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotate(Some(RotateMethod::FileSize(2)));
+    builder.set_rotation_sequence(true);
+    builder.set_timestamp_precision(TimestampPrecision::Seconds);
+    builder.set_clock(unsynced_clock);
+    builder.set_min_valid_time(Some(fixed_clock() - chrono::Duration::days(365)));
+
+    let mut logger = builder.build().unwrap();
+
+    let new_file = logger.write_line("Log").unwrap().unwrap();
+
+    // the sequence of the existing rotated log file in seconds continues
+    assert_eq!(
+        "logfile-2000-01-01-00-00-00-001.log",
+        new_file.file_name().unwrap().to_str().unwrap()
+    );
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_rotation_naming_template() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    // found by the template, so it is removed by the count retention
+    fs::write(Path::join(&test_folder, Path::new("logfile.19991231.log")), "Old.").unwrap();
+
+    let new_files = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_count(Some(3));
+        builder.set_rotation_naming(RotationNaming::Template(".%Y%m%d".to_string()));
+        builder.set_clock(fixed_clock);
+
+        let mut logger = builder.build().unwrap();
+
+        let mut new_files = Vec::new();
+
+        for _ in 0..3 {
+            logger.write_line("This is a log.").unwrap();
+            new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+        }
+
+        new_files
+    };
+
+    let names: Vec<&str> =
+        new_files.iter().map(|path| path.file_name().unwrap().to_str().unwrap()).collect();
+
+    assert_eq!(
+        vec!["logfile.20000101.log", "logfile.20000101-001.log", "logfile.20000101-002.log"],
+        names
+    );
+
+    assert!(!new_files[0].exists());
+    assert_eq!(3, test_folder.read_dir().unwrap().count());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_rotation_naming_sequential() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(10)));
+        builder.set_count(Some(3));
+        builder.set_rotation_naming(RotationNaming::Sequential);
+
+        let mut logger = builder.build().unwrap();
+
+        for i in 1..=3 {
+            let new_file = logger.write_line(format!("Log {:05}.", i)).unwrap().unwrap();
+
+            assert_eq!("logfile.log.1", new_file.file_name().unwrap());
+        }
+    }
+
+    // the oldest one is removed
+    assert_eq!(3, test_folder.read_dir().unwrap().count());
+
+    assert_eq!(
+        "Log 00003.",
+        fs::read_to_string(Path::join(&test_folder, "logfile.log.1")).unwrap()
+    );
+    assert_eq!(
+        "Log 00002.",
+        fs::read_to_string(Path::join(&test_folder, "logfile.log.2")).unwrap()
+    );
+
+    // found after a restart
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(10)));
+        builder.set_count(Some(3));
+        builder.set_rotation_naming(RotationNaming::Sequential);
+        builder.set_compress(true);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("Log 00004.").unwrap().unwrap();
+    }
+
+    assert_eq!(3, test_folder.read_dir().unwrap().count());
+
+    assert!(Path::join(&test_folder, "logfile.log.1.xz").exists());
+    assert_eq!(
+        "Log 00003.",
+        fs::read_to_string(Path::join(&test_folder, "logfile.log.2")).unwrap()
+    );
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_count() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let new_file = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_count(Some(5));
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Isn't it?").unwrap().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Isn't it?").unwrap().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Isn't it?").unwrap().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Isn't it?").unwrap().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Isn't it?").unwrap().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        let new_file = logger.write_line("Isn't it?").unwrap().unwrap();
+
+        logger.write_line("New file!!!!").unwrap();
+
+        new_file
+    };
+
+    assert_eq!(5, test_folder.read_dir().unwrap().count());
+
+    let string_1 = fs::read_to_string(test_log_path).unwrap();
+
+    assert_eq!("New file!!!!\n", string_1);
+
+    let string_2 = fs::read_to_string(new_file).unwrap();
+
+    assert_eq!("This is a log.\nIsn't it?", string_2);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_max_age() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let old_rotated_log_path =
+        Path::join(&test_folder, Path::new("logfile-2000-01-01-00-00-00-000.log"));
+    let old_rotated_log_path_compressed =
+        Path::join(&test_folder, Path::new("logfile-2000-01-02-00-00-00-000.log.xz"));
+
+    fs::write(&old_rotated_log_path, "Old.").unwrap();
+    fs::write(&old_rotated_log_path_compressed, "Old.").unwrap();
+
+    let new_file = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_max_age(Some(Duration::from_secs(24 * 60 * 60)));
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Isn't it?").unwrap().unwrap()
+    };
+
+    assert!(!old_rotated_log_path.exists());
+    assert!(!old_rotated_log_path_compressed.exists());
+
+    assert_eq!("This is a log.\nIsn't it?", fs::read_to_string(new_file).unwrap());
+
+    assert_eq!(2, test_folder.read_dir().unwrap().count());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_max_total_size() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let new_file = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_max_total_size(Some(60));
+
+        let mut logger = builder.build().unwrap();
+
+        for _ in 0..4 {
+            logger.write_line("This is a log.").unwrap();
+            logger.write_line("Isn't it?").unwrap().unwrap();
+        }
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Isn't it?").unwrap().unwrap()
+    };
+
+    // two rotated log files of 24 bytes fit in the budget
+    assert_eq!(3, test_folder.read_dir().unwrap().count());
+
+    assert_eq!("This is a log.\nIsn't it?", fs::read_to_string(new_file).unwrap());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_on_build() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let file = fs::File::create(&test_log_path).unwrap();
+    std::io::Write::write_all(&mut &file, b"Logs from the last run.\n").unwrap();
+    file.set_modified(std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(946684800)).unwrap();
+    drop(file);
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotate(Some(RotateMethod::FileSize(1024)));
+    builder.set_rotate_on_build(true);
+
+    // not oversized
+    builder.clone().build().unwrap();
+
+    assert_eq!(1, test_folder.read_dir().unwrap().count());
+
+    builder.set_rotate(Some(RotateMethod::FileSize(10)));
+
+    let mut logger = builder.build().unwrap();
+
+    let rotated_log_file = Path::join(&test_folder, "logfile-2000-01-01-00-00-00-000.log");
+
+    assert_eq!("Logs from the last run.\n", fs::read_to_string(rotated_log_file).unwrap());
+    assert_eq!(0, logger.file_size());
+
+    logger.write_line("New").unwrap();
+
+    assert_eq!("New\n", fs::read_to_string(&test_log_path).unwrap());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_count_background_retention() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let mut new_files = Vec::new();
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotate(Some(RotateMethod::FileSize(24)));
+    builder.set_count(Some(3));
+    builder.set_compress(true);
+    builder.set_background_retention(true);
+
+    let mut logger = builder.build().unwrap();
+
+    for _ in 0..4 {
+        logger.write_line("This is a log.").unwrap();
+        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+    }
+
+    assert!(logger.close(Duration::from_secs(10)).is_complete());
+
+    assert!(!new_files[0].exists());
+    assert!(!new_files[1].exists());
+    assert!(new_files[2].exists());
+    assert!(new_files[3].exists());
+
+    assert_eq!(3, test_folder.read_dir().unwrap().count());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_count_pinned() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let (pinned_file, unpinned_file) = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_count(Some(2));
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        let pinned_file = logger.write_line("Isn't it?").unwrap().unwrap();
+
+        assert!(logger.pin(&pinned_file));
+
+        logger.write_line("This is a log.").unwrap();
+        let unpinned_file = logger.write_line("Isn't it?").unwrap().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Isn't it?").unwrap().unwrap();
+
+        assert!(logger.unpin(&pinned_file));
+        assert!(!logger.unpin(&unpinned_file));
+
+        (pinned_file, unpinned_file)
+    };
+
+    assert!(pinned_file.exists());
+    assert!(!unpinned_file.exists());
+
+    assert_eq!(3, test_folder.read_dir().unwrap().count());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_thinning() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    // `fixed_clock` is at 2000-01-01 00:00:00
+    let removed = [
+        "logfile-1999-11-01-00-00-00-000.log",
+        "logfile-1999-12-20-20-00-00-000.log",
+        "logfile-1999-12-31-10-40-00-000.log",
+    ];
+
+    let kept = [
+        "logfile-1999-12-20-03-00-00-000.log",
+        "logfile-1999-12-21-05-00-00-000.log",
+        "logfile-1999-12-31-10-05-00-000.log",
+        "logfile-1999-12-31-11-00-00-000.log",
+        "logfile-1999-12-31-23-30-00-000.log",
+        "logfile-1999-12-31-23-45-00-000.log",
+    ];
+
+    for name in removed.iter().chain(kept.iter()) {
+        fs::write(Path::join(&test_folder, name), "Old log.\n").unwrap();
+    }
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotate(Some(RotateMethod::FileSize(2)));
+    builder.set_thinning(Some(Thinning::default()));
+    builder.set_deterministic(true);
+    builder.set_clock(fixed_clock);
+
+    let mut logger = builder.build().unwrap();
+
+    let new_file = logger.write_line("Log").unwrap().unwrap();
+
+    assert!(new_file.exists());
+
+    for name in removed.iter() {
+        assert!(!Path::join(&test_folder, name).exists(), "{}", name);
+    }
+
+    for name in kept.iter() {
+        assert!(Path::join(&test_folder, name).exists(), "{}", name);
+    }
+
+    assert_eq!(kept.len() + 2, test_folder.read_dir().unwrap().count());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_compaction() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(2)));
+        builder.set_compaction_size(Some(6));
+        builder.set_deterministic(true);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write("a\n").unwrap().unwrap();
+        logger.write("b\n").unwrap().unwrap();
+        let merged_file = logger.write("c\n").unwrap().unwrap();
+        let new_file = logger.write("d\n").unwrap().unwrap();
+
+        // 4 bytes of `a` and `b` are merged with `c`, and then 6 bytes are too big to be merged with `d`
+        assert_eq!("a\nb\nc\n", fs::read_to_string(merged_file).unwrap());
+        assert_eq!("d\n", fs::read_to_string(new_file).unwrap());
+
+        assert_eq!(3, test_folder.read_dir().unwrap().count());
+    }
+
+    fs::remove_dir_all(&test_folder).unwrap();
+    fs::create_dir_all(&test_folder).unwrap();
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(2)));
+        builder.set_compress(true);
+        builder.set_compaction_size(Some(1024));
+        builder.set_deterministic(true);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write("a\n").unwrap().unwrap();
+        logger.write("b\n").unwrap().unwrap();
+        let merged_file = logger.write("c\n").unwrap().unwrap();
+
+        assert!(merged_file.to_str().unwrap().ends_with(".xz"));
+
+        assert_eq!(2, test_folder.read_dir().unwrap().count());
+    }
+
+    let searcher = PipeLogSearcher::new(&test_log_path).unwrap();
+
+    let lines: Vec<String> = searcher.grep("", None).unwrap().map(|m| m.unwrap().line).collect();
+
+    assert_eq!(vec!["a", "b", "c"], lines);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn doctor() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let report =
+        PipeLogger::doctor(&PipeLogger::builder(Path::join(&test_folder, "missing/logfile.log")));
+
+    assert!(!report.is_healthy());
+
+    let partial = Path::join(&test_folder, "logfile-1999-12-31-00-00-00-000.log");
+    let future = Path::join(&test_folder, "logfile-2000-01-02-00-00-00-000.log");
+    let misnamed = Path::join(&test_folder, "logfile-1999-12-30.log");
+
+    fs::write(&partial, "Log\n").unwrap();
+    fs::write(format!("{}.xz", partial.to_str().unwrap()), "").unwrap();
+    fs::write(&future, "Log\n").unwrap();
+    fs::write(&misnamed, "Log\n").unwrap();
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotate(Some(RotateMethod::FileSize(1024)));
+    builder.set_clock(fixed_clock);
+
+    let report = PipeLogger::doctor(&builder);
+
+    assert!(report.is_healthy());
+
+    let problems: Vec<(DoctorCheck, Option<&Path>)> =
+        report.problems().map(|finding| (finding.check, finding.path.as_deref())).collect();
+
+    assert!(problems.iter().any(|(check, path)| *check == DoctorCheck::PartialCompression
+        && path.unwrap().to_str().unwrap().ends_with("000.log.xz")));
+    assert!(problems.iter().any(|(check, path)| *check == DoctorCheck::Naming
+        && path.unwrap().ends_with("logfile-1999-12-30.log")));
+    assert!(problems.iter().any(|(check, _)| *check == DoctorCheck::Clock));
+    assert_eq!(3, problems.len());
+
+    // the log file is not created
+    assert!(!test_log_path.exists());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn doctor_with_child_loggers() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotate(Some(RotateMethod::FileSize(24)));
+
+    {
+        let mut router = DynamicRouter::new(builder.clone().build().unwrap(), |text| {
+            text.find(':').map(|index| text[..index].to_string())
+        });
+
+        router.write_line("a: This is a log.").unwrap();
+        router.write_line("a: Isn't it?").unwrap().unwrap();
+        router.write_line("b2: This is a log.").unwrap();
+        router.write_line("b2: Isn't it?").unwrap().unwrap();
+    }
+
+    // two child log files and their rotated log files
+    assert_eq!(5, test_folder.read_dir().unwrap().count());
+
+    // a rotated log file of a child logger whose log file has been removed
+    fs::write(Path::join(&test_folder, "logfile-7-2000-01-01-00-00-00-000.log"), "Log\n").unwrap();
+
+    let report = PipeLogger::doctor(&builder);
+
+    assert_eq!(0, report.problems().count());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn doctor_with_sequential_naming() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    fs::write(Path::join(&test_folder, "logfile.log.1"), "Log\n").unwrap();
+    fs::write(Path::join(&test_folder, "logfile.log.2.xz"), "").unwrap();
+    fs::write(Path::join(&test_folder, "logfile.log.02"), "Log\n").unwrap();
+    fs::write(Path::join(&test_folder, "logfile-1999-12-31-00-00-00-000.log"), "Log\n").unwrap();
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotation_naming(RotationNaming::Sequential);
+
+    let report = PipeLogger::doctor(&builder);
+
+    let problems: Vec<(DoctorCheck, Option<&Path>)> =
+        report.problems().map(|finding| (finding.check, finding.path.as_deref())).collect();
+
+    assert_eq!(1, problems.len());
+    assert_eq!(DoctorCheck::Naming, problems[0].0);
+    assert!(problems[0].1.unwrap().ends_with("logfile.log.02"));
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_long_name() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, format!("{}.log", "a".repeat(240)));
+
+    let mut new_files = Vec::new();
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(2)));
+        builder.set_count(Some(3));
+        builder.set_compress(true);
+        builder.set_deterministic(true);
+
+        let mut logger = builder.build().unwrap();
+
+        for _ in 0..2 {
+            new_files.push(logger.write("Log\n").unwrap().unwrap());
+        }
+    }
+
+    for new_file in new_files.iter() {
+        let name = new_file.file_name().unwrap().to_str().unwrap();
+
+        assert!(name.len() <= 255);
+        assert!(name.contains('~'));
+        assert!(name.ends_with(".log.xz"));
+    }
+
+    // another logger derives the same names, so the count retention still works
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(2)));
+        builder.set_count(Some(3));
+        builder.set_compress(true);
+        builder.set_deterministic(true);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write("Log\n").unwrap().unwrap();
+    }
+
+    assert!(!new_files[0].exists());
+    assert!(new_files[1].exists());
+
+    assert_eq!(3, test_folder.read_dir().unwrap().count());
+
+    let searcher = PipeLogSearcher::new(&test_log_path).unwrap();
+
+    assert_eq!(2, searcher.grep("^Log$", None).unwrap().count());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_compress() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let mut new_files = Vec::new();
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_compress(true);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+
+        logger.write_line("This is a log.").unwrap();
+        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+
+        logger.write_line("This is a log.").unwrap();
+        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+
+        logger.write_line("This is a log.").unwrap();
+        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+
+        logger.write_line("This is a log.").unwrap();
+        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+
+        logger.write_line("This is a log.").unwrap();
+        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+
+        logger.write_line("New file!!!!").unwrap();
+    };
+
+    // the background worker is joined when the logger is dropped
+    assert_eq!(7, test_folder.read_dir().unwrap().count());
+
+    for new_file in new_files {
+        assert!(new_file.exists());
+
+        assert!(new_file.to_str().unwrap().ends_with(".xz"));
+    }
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_compress_flush_background() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotate(Some(RotateMethod::FileSize(24)));
+    builder.set_compress(true);
+    builder.add_processor(SlowProcessor);
+
+    let mut logger = builder.build().unwrap();
+
+    logger.write_line("This is a log.").unwrap();
+    let new_file = logger.write_line("Isn't it?").unwrap().unwrap();
+
+    logger.flush_background();
+
+    assert!(new_file.exists());
+    assert!(!new_file.with_extension("").exists());
+
+    assert_eq!(2, test_folder.read_dir().unwrap().count());
+
+    drop(logger);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+fn write_rotate_with_compress_method(compress_method: CompressionMethod) {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotate(Some(RotateMethod::FileSize(2)));
+    builder.set_compress(true);
+    builder.set_compress_method(compress_method);
+    builder.set_compression_verify(true);
+    builder.set_deterministic(true);
+
+    let mut logger = builder.build().unwrap();
+
+    let new_file_1 = logger.write_line("Log").unwrap().unwrap();
+    let new_file_2 = logger.write_line("Log").unwrap().unwrap();
+
+    assert!(new_file_1.to_str().unwrap().ends_with(compress_method.extension()));
+    assert!(new_file_2.exists());
+
+    let searcher = PipeLogSearcher::new(&test_log_path).unwrap();
+
+    assert_eq!(2, searcher.grep("^Log$", None).unwrap().count());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_compress_xz() {
+    write_rotate_with_compress_method(CompressionMethod::Xz);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn write_rotate_with_compress_gzip() {
+    write_rotate_with_compress_method(CompressionMethod::Gzip);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn write_rotate_with_compress_zstd() {
+    write_rotate_with_compress_method(CompressionMethod::Zstd);
+}
+
+#[test]
+fn write_rotate_with_count_other_compress_methods() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    // compressed by builds with other features
+    fs::write(Path::join(&test_folder, "logfile-2000-01-01-00-00-00-000.log.gz"), "").unwrap();
+    fs::write(Path::join(&test_folder, "logfile-2000-01-01-00-00-00-001.log.zst"), "").unwrap();
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotate(Some(RotateMethod::FileSize(2)));
+    builder.set_count(Some(2));
+    builder.set_deterministic(true);
+
+    let mut logger = builder.build().unwrap();
+
+    let new_file = logger.write_line("Log").unwrap().unwrap();
+
+    let mut file_names: Vec<String> = test_folder
+        .read_dir()
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+
+    file_names.sort();
+
+    assert_eq!(
+        vec![
+            new_file.file_name().unwrap().to_str().unwrap().to_string(),
+            LOG_FILE_NAME.to_string()
+        ],
+        file_names
+    );
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_count_compress() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let mut new_files = Vec::new();
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_count(Some(5));
+        builder.set_compress(true);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+
+        logger.write_line("This is a log.").unwrap();
+        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+
+        logger.write_line("This is a log.").unwrap();
+        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+
+        logger.write_line("This is a log.").unwrap();
+        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+
+        logger.write_line("This is a log.").unwrap();
+        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+
+        logger.write_line("This is a log.").unwrap();
+        new_files.push(logger.write_line("Isn't it?").unwrap().unwrap());
+
+        logger.write_line("New file!!!!").unwrap();
+    };
+
+    // the background worker is joined when the logger is dropped
+    assert_eq!(5, test_folder.read_dir().unwrap().count());
+
+    for new_file in new_files.iter().skip(2) {
+        assert!(new_file.exists());
+
+        assert!(new_file.to_str().unwrap().ends_with(".xz"));
+    }
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_compress_multi_threads() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let new_file = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_compress(true);
+        builder.set_compression_threads(0);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        let new_file = logger.write_line("Isn't it?").unwrap().unwrap();
+
+        logger.write_line("New file!!!!").unwrap();
+
+        new_file
+    };
+
+    // the background worker is joined when the logger is dropped
+    assert_eq!(2, test_folder.read_dir().unwrap().count());
+
+    assert!(new_file.exists());
+
+    assert!(new_file.to_str().unwrap().ends_with(".xz"));
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_compress_adaptive_level() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let new_file = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_compress(true);
+        builder.set_compression_level(CompressionLevel::Adaptive(AdaptiveCompressionLevel {
+            fast_level:          0,
+            max_level:           9,
+            min_free_space:      1024,
+            min_free_file_sizes: 2,
+        }));
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        let new_file = logger.write_line("Isn't it?").unwrap().unwrap();
+
+        assert!(logger.close(Duration::from_secs(10)).is_complete());
+
+        new_file
+    };
+
+    let mut string = String::new();
+
+    xz2::read::XzDecoder::new(fs::File::open(new_file).unwrap())
+        .read_to_string(&mut string)
+        .unwrap();
+
+    assert_eq!("This is a log.\nIsn't it?", string);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_compress_tail() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let new_file = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_compress(true);
+        builder.set_compression_tail(Some(10));
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        let new_file = logger.write_line("Isn't it?").unwrap().unwrap();
+
+        logger.write_line("New file!!!!").unwrap();
+
+        new_file
+    };
+
+    // the background worker is joined when the logger is dropped
+    assert_eq!(2, test_folder.read_dir().unwrap().count());
+
+    let mut string = String::new();
+
+    xz2::read::XzDecoder::new(fs::File::open(new_file).unwrap())
+        .read_to_string(&mut string)
+        .unwrap();
+
+    assert_eq!("[pipe-logger: 14 bytes truncated]\n\nIsn't it?", string);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[derive(Debug)]
+struct RecordProcessor(Arc<Mutex<Vec<PathBuf>>>);
+
+impl RotationProcessor for RecordProcessor {
+    fn process(&self, rotated_log_file: &Path) -> std::io::Result<PathBuf> {
+        self.0.lock().unwrap().push(rotated_log_file.to_path_buf());
+
+        Ok(rotated_log_file.to_path_buf())
+    }
+}
+
+#[test]
+fn write_rotate_with_processors() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let processed = Arc::new(Mutex::new(Vec::new()));
+
+    let new_file = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_compress(true);
+        builder.add_processor(RecordProcessor(processed.clone()));
+
+        let logger = builder.build().unwrap();
+
+        let mut logger = logger.child("child").unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        let new_file = logger.write_line("Isn't it?").unwrap().unwrap();
+
+        logger.close(Duration::from_secs(10));
+
+        new_file
+    };
+
+    assert_eq!(vec![new_file], *processed.lock().unwrap());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[derive(Debug)]
+struct MetaProcessor(Arc<Mutex<Vec<BTreeMap<String, String>>>>);
+
+impl RotationProcessor for MetaProcessor {
+    fn process(&self, rotated_log_file: &Path) -> std::io::Result<PathBuf> {
+        Ok(rotated_log_file.to_path_buf())
+    }
+
+    fn process_with_meta(
+        &self,
+        rotated_log_file: &Path,
+        meta: &BTreeMap<String, String>,
+    ) -> std::io::Result<PathBuf> {
+        self.0.lock().unwrap().push(meta.clone());
+
+        Ok(rotated_log_file.to_path_buf())
+    }
+}
+
+#[test]
+fn write_rotate_with_meta() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let metas = Arc::new(Mutex::new(Vec::new()));
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotate(Some(RotateMethod::FileSize(2)));
+    builder.set_deterministic(true);
+    builder.add_processor(MetaProcessor(metas.clone()));
+
+    let mut logger = builder.build().unwrap();
+
+    let meta = BTreeMap::from([("build_id".to_string(), "1234".to_string())]);
+
+    logger.set_next_rotation_meta(meta.clone());
+
+    logger.write_line("Log").unwrap().unwrap();
+    logger.write_line("Log").unwrap().unwrap();
+
+    // the metadata is attached to the next rotation only
+    assert_eq!(vec![meta, BTreeMap::new()], *metas.lock().unwrap());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_markers() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+    let marker_path = Path::join(&test_folder, Path::new("rotated"));
+    let fifo_path = Path::join(&test_folder, Path::new("rotated.list"));
+
+    // a FIFO is never created by the logger, and a regular file is appended instead
+    fs::File::create(&fifo_path).unwrap();
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotate(Some(RotateMethod::FileSize(2)));
+    builder.set_compress(true);
+    builder.set_deterministic(true);
+    builder.add_processor(RotationMarker::Touch(marker_path.clone()));
+    builder.add_processor(RotationMarker::Fifo(fifo_path.clone()));
+
+    let mut logger = builder.build().unwrap();
+
+    let new_file_1 = logger.write_line("Log").unwrap().unwrap();
+    let new_file_2 = logger.write_line("Log").unwrap().unwrap();
+
+    assert_eq!(
+        format!("{}\n", new_file_2.to_str().unwrap()),
+        fs::read_to_string(marker_path).unwrap()
+    );
+
+    assert_eq!(
+        format!("{}\n{}\n", new_file_1.to_str().unwrap(), new_file_2.to_str().unwrap()),
+        fs::read_to_string(fifo_path).unwrap()
+    );
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_mirror() {
+    let test_folder = create_test_folder();
+    let mirror_folder = create_test_folder();
+
+    fs::remove_dir(&mirror_folder).unwrap();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let mirror = Arc::new(Mirror::new(&mirror_folder, 1));
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotate(Some(RotateMethod::FileSize(2)));
+    builder.set_compress(true);
+    builder.set_deterministic(true);
+    builder.set_processors(vec![mirror.clone()]);
+
+    let mut logger = builder.build().unwrap();
+
+    // the secondary directory is not mounted
+    logger.write_line("Log").unwrap().unwrap();
+    let new_file_2 = logger.write_line("Log").unwrap().unwrap();
+
+    assert_eq!(vec![new_file_2.clone()], mirror.backlog());
+    assert_eq!(1, mirror.dropped_count());
+
+    fs::create_dir(&mirror_folder).unwrap();
+
+    let new_file_3 = logger.write_line("Log").unwrap().unwrap();
+
+    assert!(mirror.backlog().is_empty());
+    assert_eq!(2, mirror_folder.read_dir().unwrap().count());
+
+    for new_file in [new_file_2, new_file_3] {
+        assert_eq!(
+            fs::read(&new_file).unwrap(),
+            fs::read(Path::join(&mirror_folder, new_file.file_name().unwrap())).unwrap()
+        );
+    }
+
+    fs::remove_dir_all(test_folder).unwrap();
+    fs::remove_dir_all(mirror_folder).unwrap();
+}
+
+#[derive(Debug)]
+struct FlakyProcessor(AtomicUsize);
+
+impl RotationProcessor for FlakyProcessor {
+    fn process(&self, rotated_log_file: &Path) -> std::io::Result<PathBuf> {
+        // succeed on the third attempt only
+        if self.0.fetch_add(1, Ordering::SeqCst) == 2 {
+            Ok(rotated_log_file.to_path_buf())
+        } else {
+            Err(std::io::Error::new(std::io::ErrorKind::Other, "upload failed"))
+        }
+    }
+}
+
+#[test]
+fn write_rotate_with_dead_letter() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+    let dead_letter_dir = Path::join(&test_folder, Path::new("dead"));
+
+    let dead_letters = Arc::new(Mutex::new(Vec::new()));
+
+    let (new_file_1, new_file_2) = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_processor_retries(2);
+        builder.set_retry_delay(Duration::from_millis(10));
+        builder.set_dead_letter_dir(Some(dead_letter_dir.clone()));
+        builder.add_processor(FlakyProcessor(AtomicUsize::new(0)));
+
+        let mut logger = builder.build().unwrap();
+
+        let callback_dead_letters = dead_letters.clone();
+
+        logger.set_dead_letter_callback(Box::new(move |dead_letter| {
+            callback_dead_letters.lock().unwrap().push(dead_letter.path);
+        }));
+
+        logger.write_line("This is a log.").unwrap();
+        let new_file_1 = logger.write_line("Isn't it?").unwrap().unwrap();
+
+        assert!(logger.flush_within(Duration::from_secs(10)).unwrap().is_complete());
+
+        logger.write_line("This is a log.").unwrap();
+        let new_file_2 = logger.write_line("Isn't it?").unwrap().unwrap();
+
+        assert!(logger.close(Duration::from_secs(10)).is_complete());
+
+        (new_file_1, new_file_2)
+    };
+
+    // succeeded after two retries
+    assert!(new_file_1.exists());
+
+    // failed all of the retries
+    assert!(!new_file_2.exists());
+
+    let parked = Path::join(&dead_letter_dir, new_file_2.file_name().unwrap());
+
+    assert_eq!(vec![parked.clone()], *dead_letters.lock().unwrap());
+    assert_eq!("This is a log.\nIsn't it?", fs::read_to_string(&parked).unwrap());
+
+    let mut sidecar = parked.into_os_string();
+    sidecar.push(".error");
+
+    assert!(fs::read_to_string(sidecar).unwrap().contains("upload failed"));
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_rotate_with_compress_verify() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let new_file = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_compress(true);
+        builder.set_compression_tail(Some(10));
+        builder.set_compression_verify(true);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        let new_file = logger.write_line("Isn't it?").unwrap().unwrap();
+
+        assert!(logger.close(Duration::from_secs(10)).is_complete());
+
+        new_file
+    };
+
+    assert_eq!(2, test_folder.read_dir().unwrap().count());
+
+    let mut string = String::new();
+
+    xz2::read::XzDecoder::new(fs::File::open(new_file).unwrap())
+        .read_to_string(&mut string)
+        .unwrap();
+
+    assert_eq!("[pipe-logger: 14 bytes truncated]\n\nIsn't it?", string);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn close() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let new_file = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_compress(true);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        let new_file = logger.write_line("Isn't it?").unwrap().unwrap();
+
+        logger.write_line("New file!!!!").unwrap();
+
+        let report = logger.close(Duration::from_millis(WAIT_DURATION_MILLI_SECONDS * 5));
+
+        assert!(report.is_complete());
+
+        new_file
+    };
+
+    assert_eq!(2, test_folder.read_dir().unwrap().count());
+
+    assert!(new_file.exists());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn close_with_final_marker() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_final_marker(Some("-- end of log --".to_string()));
+    builder.set_rotate_on_close(true);
+    builder.set_clock(fixed_clock);
+
+    let mut logger = builder.build().unwrap();
+
+    logger.write_line("This is a log.").unwrap();
+
+    let report = logger.close(Duration::from_millis(WAIT_DURATION_MILLI_SECONDS * 5));
+
+    assert!(report.is_complete());
+
+    let rotated_log_file = report.rotated_log_file.unwrap();
+
+    assert_eq!(
+        "logfile-2000-01-01-00-00-00-000.log",
+        rotated_log_file.file_name().unwrap().to_str().unwrap()
+    );
+    assert_eq!("This is a log.\n-- end of log --\n", fs::read_to_string(rotated_log_file).unwrap());
+    assert_eq!(0, fs::metadata(&test_log_path).unwrap().len());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn snapshot() {
+    let test_folder = create_test_folder();
+    let snapshot_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Isn't it?").unwrap().unwrap();
+
+        logger.write_line("New file!!!!").unwrap();
+
+        let snapshot_files = logger.snapshot(&snapshot_folder).unwrap();
+
+        assert_eq!(2, snapshot_files.len());
+
+        logger.write_line("After the snapshot.").unwrap();
+    }
+
+    assert_eq!(2, snapshot_folder.read_dir().unwrap().count());
+
+    let string =
+        fs::read_to_string(Path::join(&snapshot_folder, Path::new(LOG_FILE_NAME))).unwrap();
+
+    assert_eq!("New file!!!!\n", string);
+
+    fs::remove_dir_all(test_folder).unwrap();
+    fs::remove_dir_all(snapshot_folder).unwrap();
+}
+
+#[test]
+fn write_external_rotation() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_external_rotation(true);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        assert!(logger.write_line("Isn't it?").unwrap().is_none());
+
+        assert_eq!(25, logger.file_size());
+
+        // copytruncate
+        fs::copy(&test_log_path, Path::join(&test_folder, Path::new("logfile.log.1"))).unwrap();
+        fs::OpenOptions::new().write(true).truncate(true).open(&test_log_path).unwrap();
+
+        logger.write_line("New file!!!!").unwrap();
+
+        assert_eq!(13, logger.file_size());
+    }
+
+    assert_eq!(2, test_folder.read_dir().unwrap().count());
+
+    let string = fs::read_to_string(test_log_path).unwrap();
+
+    assert_eq!("New file!!!!\n", string);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn write_reopen_on_move() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+    let moved_log_path = Path::join(&test_folder, Path::new("logfile.log.1"));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_reopen_on_move(true);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+
+        // logrotate without copytruncate
+        fs::rename(&test_log_path, &moved_log_path).unwrap();
+
+        logger.write_line("New file!!!!").unwrap();
+
+        assert_eq!(13, logger.file_size());
+
+        fs::remove_file(&test_log_path).unwrap();
+
+        logger.write_line("Newer file!!!!").unwrap();
+
+        assert_eq!(15, logger.file_size());
+    }
+
+    assert_eq!("This is a log.\n", fs::read_to_string(&moved_log_path).unwrap());
+    assert_eq!("Newer file!!!!\n", fs::read_to_string(&test_log_path).unwrap());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn reopen() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+    let moved_log_path = Path::join(&test_folder, Path::new("logfile.log.1"));
+
+    {
+        let mut logger = PipeLoggerBuilder::new(&test_log_path).build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+
+        fs::rename(&test_log_path, &moved_log_path).unwrap();
+
+        // the moved log file is still written without reopening it
+        logger.write_line("Isn't it?").unwrap();
+
+        logger.reopen().unwrap();
+
+        logger.write_line("New file!!!!").unwrap();
+    }
+
+    assert_eq!("This is a log.\nIsn't it?\n", fs::read_to_string(&moved_log_path).unwrap());
+    assert_eq!("New file!!!!\n", fs::read_to_string(&test_log_path).unwrap());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn write_after_failed_rotation() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut logger = PipeLoggerBuilder::new(&test_log_path).build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+
+        fs::remove_file(&test_log_path).unwrap();
+
+        // the log file to be renamed is gone after its handle has been closed
+        assert!(logger.rotate().is_err());
+
+        logger.write_line("Isn't it?").unwrap();
+
+        logger.rotate().unwrap();
+
+        logger.write_line("New file!!!!").unwrap();
+    }
+
+    assert_eq!("New file!!!!\n", fs::read_to_string(&test_log_path).unwrap());
+    assert_eq!(2, fs::read_dir(&test_folder).unwrap().count());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn recover_interrupted_rotation() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+    let journal_path = Path::join(&test_folder, Path::new(".logfile.log.rotation"));
+    let rotated_log_path =
+        Path::join(&test_folder, Path::new("logfile-2000-01-01-00-00-00-000.log"));
+    let copying_path =
+        Path::join(&test_folder, Path::new(".logfile-2000-01-01-00-00-00-000.log.copying"));
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_rotate(Some(RotateMethod::FileSize(24)));
+
+    // crashed while copying
+    fs::write(&test_log_path, "This is a log.\nIsn't it?").unwrap();
+    fs::write(&journal_path, "logfile-2000-01-01-00-00-00-000.log").unwrap();
+    fs::write(&copying_path, "This is a log.\nIsn't").unwrap();
+
+    {
+        let mut logger = builder.clone().build().unwrap();
+
+        assert!(!journal_path.exists());
+        assert!(!copying_path.exists());
+        assert!(!rotated_log_path.exists());
+
+        let new_file = logger.write("\n").unwrap().unwrap();
+
+        assert_eq!("This is a log.\nIsn't it?\n", fs::read_to_string(new_file).unwrap());
+    }
+
+    fs::remove_dir_all(&test_folder).unwrap();
+    fs::create_dir_all(&test_folder).unwrap();
+
+    // crashed after copying but before truncating
+    fs::write(&test_log_path, "This is a log.\nIsn't it?").unwrap();
+    fs::write(&journal_path, "logfile-2000-01-01-00-00-00-000.log").unwrap();
+    fs::write(&rotated_log_path, "This is a log.\nIsn't it?").unwrap();
+
+    {
+        let mut logger = builder.build().unwrap();
+
+        assert!(!journal_path.exists());
+
+        logger.write_line("New file!!!!").unwrap();
+    }
+
+    assert_eq!("This is a log.\nIsn't it?", fs::read_to_string(&rotated_log_path).unwrap());
+    assert_eq!("New file!!!!\n", fs::read_to_string(&test_log_path).unwrap());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn recover_repeated_lines() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let rotated_log_file = {
+        let mut logger = PipeLoggerBuilder::new(&test_log_path).build().unwrap();
+
+        logger.write_line("ping").unwrap();
+
+        let rotated_log_file = logger.rotate().unwrap().unwrap();
+
+        logger.write_line("ping").unwrap();
+        logger.write_line("pong").unwrap();
+
+        rotated_log_file
+    };
+
+    // a rotated log file which happens to be a prefix of the log file is not an interrupted rotation
+    PipeLoggerBuilder::new(&test_log_path).build().unwrap();
+
+    assert_eq!("ping\n", fs::read_to_string(rotated_log_file).unwrap());
+    assert_eq!("ping\npong\n", fs::read_to_string(&test_log_path).unwrap());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn recover_pending_sequential_rotation() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    // crashed before the worker shifted the rotated log files
+    fs::write(Path::join(&test_folder, "logfile.log.1"), "Log 00001.").unwrap();
+    fs::write(Path::join(&test_folder, ".logfile.log.1.pending"), "Log 00002.").unwrap();
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(10)));
+        builder.set_rotation_naming(RotationNaming::Sequential);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("Log 00003.").unwrap().unwrap();
+    }
+
+    assert_eq!(4, test_folder.read_dir().unwrap().count());
+
+    for (index, content) in [(1, "Log 00003."), (2, "Log 00002."), (3, "Log 00001.")] {
+        assert_eq!(
+            content,
+            fs::read_to_string(Path::join(&test_folder, format!("logfile.log.{}", index))).unwrap()
+        );
+    }
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn recover_interrupted_compression() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+    let rotated_log_path =
+        Path::join(&test_folder, Path::new("logfile-2000-01-01-00-00-00-000.log"));
+    let rotated_log_path_compressed =
+        Path::join(&test_folder, Path::new("logfile-2000-01-01-00-00-00-000.log.xz"));
+
+    // crashed while compressing
+    fs::write(&rotated_log_path, "This is a log.\nIsn't it?").unwrap();
+    fs::write(&rotated_log_path_compressed, "partial").unwrap();
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_compress(true);
+
+        let logger = builder.build().unwrap();
+
+        assert!(logger.close(Duration::from_millis(WAIT_DURATION_MILLI_SECONDS * 5)).is_complete());
+    }
+
+    assert!(!rotated_log_path.exists());
+
+    let mut string = String::new();
+
+    xz2::read::XzDecoder::new(fs::File::open(rotated_log_path_compressed).unwrap())
+        .read_to_string(&mut string)
+        .unwrap();
+
+    assert_eq!("This is a log.\nIsn't it?", string);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn child() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+    let test_child_log_path = Path::join(&test_folder, Path::new("logfile-child.log"));
+
+    let new_file = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+
+        let mut logger = builder.build().unwrap();
+
+        let mut child = logger.child("child").unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+
+        child.write_line("This is a log.").unwrap();
+        let new_file = child.write_line("Isn't it?").unwrap().unwrap();
+
+        child.write_line("New file!!!!").unwrap();
+
+        new_file
+    };
+
+    assert_eq!(3, test_folder.read_dir().unwrap().count());
+
+    assert_eq!("This is a log.\n", fs::read_to_string(test_log_path).unwrap());
+
+    assert_eq!("New file!!!!\n", fs::read_to_string(test_child_log_path).unwrap());
+
+    assert!(new_file.file_name().unwrap().to_str().unwrap().starts_with("logfile-child-"));
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn dynamic_router() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let builder = PipeLoggerBuilder::new(&test_log_path);
+
+        let mut router = DynamicRouter::new(builder.build().unwrap(), |text| {
+            text.find(':').map(|index| text[..index].to_string())
+        });
+
+        router.set_max_open_loggers(1);
+
+        router.write_line("a: This is a log.").unwrap();
+        router.write_line("b/c: This is a log.").unwrap();
+        router.write_line("a: Isn't it?").unwrap();
+        router.write_line("No key.").unwrap();
+    }
+
+    assert_eq!(3, test_folder.read_dir().unwrap().count());
+
+    assert_eq!("No key.\n", fs::read_to_string(&test_log_path).unwrap());
+
+    assert_eq!(
+        "a: This is a log.\na: Isn't it?\n",
+        fs::read_to_string(Path::join(&test_folder, Path::new("logfile-a.log"))).unwrap()
+    );
+
+    assert_eq!(
+        "b/c: This is a log.\n",
+        fs::read_to_string(Path::join(&test_folder, Path::new("logfile-b_c.log"))).unwrap()
+    );
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn dynamic_router_churn() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(9)));
+
+        let mut router = DynamicRouter::new(builder.build().unwrap(), |text| {
+            text.find(':').map(|index| text[..index].to_string())
+        });
+
+        router.set_max_open_loggers(1);
+
+        // every write closes the other child logger, and rotates the log file of the reopened one
+        for i in 0..5 {
+            router.write_line(format!("a: line {}", i)).unwrap().unwrap();
+            router.write_line(format!("b: line {}", i)).unwrap().unwrap();
+        }
+    }
+
+    for key in ["a", "b"] {
+        let prefix = format!("logfile-{}-", key);
+
+        let mut lines: Vec<String> = fs::read_dir(&test_folder)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.file_name().unwrap().to_string_lossy().starts_with(&prefix))
+            .map(|path| fs::read_to_string(path).unwrap())
+            .collect();
+
+        lines.sort();
+
+        let expected: Vec<String> = (0..5).map(|i| format!("{}: line {}", key, i)).collect();
+
+        assert_eq!(expected, lines);
+    }
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn dynamic_router_rotate_together() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+
+        let mut router = DynamicRouter::new(builder.build().unwrap(), |text| {
+            text.find(':').map(|index| text[..index].to_string())
+        });
+
+        router.set_rotate_together(true);
+
+        router.write_line("No key.").unwrap();
+        router.write_line("a: This is a log.").unwrap();
+        router.write_line("b: This is a log.").unwrap();
+        router.write_line("b: Isn't it?").unwrap().unwrap();
+
+        assert!(router.base().rotate().unwrap().is_none());
+    }
+
+    // three active log files and three rotated log files
+    assert_eq!(6, test_folder.read_dir().unwrap().count());
+
+    assert_eq!("", fs::read_to_string(&test_log_path).unwrap());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn dynamic_router_rotate_together_with_failure() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+
+        let mut router = DynamicRouter::new(builder.build().unwrap(), |text| {
+            text.find(':').map(|index| text[..index].to_string())
+        });
+
+        router.set_rotate_together(true);
+
+        router.write_line("No key.").unwrap();
+        router.write_line("a: This is a log.").unwrap();
+        router.write_line("b: This is a log.").unwrap();
+        router.write_line("c: This is a log.").unwrap();
+
+        // the log file of `a` cannot be rotated
+        fs::remove_file(Path::join(&test_folder, "logfile-a.log")).unwrap();
+
+        assert!(router.write_line("c: Isn't it?").is_err());
+    }
+
+    let names: Vec<String> = test_folder
+        .read_dir()
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+
+    // the base logger and `b` are still rotated
+    assert_eq!("", fs::read_to_string(&test_log_path).unwrap());
+    assert_eq!("", fs::read_to_string(Path::join(&test_folder, "logfile-b.log")).unwrap());
+    assert_eq!(3, names.iter().filter(|name| name.len() > "logfile-a.log".len()).count());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn dynamic_router_with_regex() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let builder = PipeLoggerBuilder::new(&test_log_path);
+
+        let mut router =
+            DynamicRouter::with_regex(builder.build().unwrap(), r"^\[([a-z]+)\]").unwrap();
+
+        router.write_line("[api] This is a log.").unwrap();
+        router.write_line("[db] This is a log.").unwrap();
+        router.write_line("[api] Isn't it?").unwrap();
+        router.write_line("No key.").unwrap();
+    }
+
+    assert_eq!(3, test_folder.read_dir().unwrap().count());
+
+    assert_eq!("No key.\n", fs::read_to_string(&test_log_path).unwrap());
+
+    assert_eq!(
+        "[api] This is a log.\n[api] Isn't it?\n",
+        fs::read_to_string(Path::join(&test_folder, Path::new("logfile-api.log"))).unwrap()
+    );
+
+    assert_eq!(
+        "[db] This is a log.\n",
+        fs::read_to_string(Path::join(&test_folder, Path::new("logfile-db.log"))).unwrap()
+    );
+
+    assert!(DynamicRouter::with_regex(
+        PipeLoggerBuilder::new(&test_log_path).build().unwrap(),
+        "("
+    )
+    .is_err());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[cfg(any(unix, windows))]
+#[test]
+fn lock() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_lock(true);
+
+        let mut logger = builder.build().unwrap();
+
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_lock(true);
+
+        assert!(matches!(builder.build(), Err(PipeLoggerBuilderError::AlreadyLocked(_))));
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Isn't it?").unwrap().unwrap();
+
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_lock(true);
+
+        assert!(matches!(builder.build(), Err(PipeLoggerBuilderError::AlreadyLocked(_))));
+    }
+
+    let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+    builder.set_lock(true);
+
+    builder.build().unwrap();
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn write_rotate_with_file_mode() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_compress(true);
+        builder.set_file_mode(Some(0o640));
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Isn't it?").unwrap().unwrap();
+
+        assert!(logger.close(Duration::from_millis(WAIT_DURATION_MILLI_SECONDS * 5)).is_complete());
+    }
+
+    assert_eq!(2, test_folder.read_dir().unwrap().count());
+
+    for entry in test_folder.read_dir().unwrap() {
+        let mode = entry.unwrap().metadata().unwrap().permissions().mode();
+
+        assert_eq!(0o640, mode & 0o777);
+    }
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn write_rotate_by_rename() {
+    use std::os::unix::fs::{symlink, MetadataExt};
+
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+
+        let mut logger = builder.build().unwrap();
+
+        let ino = fs::metadata(&test_log_path).unwrap().ino();
+
+        logger.write_line("This is a log.").unwrap();
+        let new_file = logger.write_line("Isn't it?").unwrap().unwrap();
+
+        // the log file itself becomes the rotated log file
+        assert_eq!(ino, fs::metadata(new_file).unwrap().ino());
+        assert_ne!(ino, fs::metadata(&test_log_path).unwrap().ino());
+    }
+
+    // a symbolic link is kept, and its target is copied and truncated
+    let target_folder = Path::join(&test_folder, "target");
+
+    fs::create_dir_all(&target_folder).unwrap();
+
+    let target_path = Path::join(&target_folder, LOG_FILE_NAME);
+    let link_path = Path::join(&test_folder, "link.log");
+
+    fs::write(&target_path, "").unwrap();
+    symlink(fs::canonicalize(&target_path).unwrap(), &link_path).unwrap();
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&link_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_follow_symlinks(true);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        let new_file = logger.write_line("Isn't it?").unwrap().unwrap();
+
+        logger.write_line("New file!!!!").unwrap();
+
+        assert_eq!("This is a log.\nIsn't it?", fs::read_to_string(new_file).unwrap());
+    }
+
+    assert!(fs::symlink_metadata(&link_path).unwrap().file_type().is_symlink());
+    assert_eq!("New file!!!!\n", fs::read_to_string(&target_path).unwrap());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[cfg(all(feature = "xattr", target_os = "linux"))]
+#[test]
+fn write_rotate_with_xattr() {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let name = CString::new("user.pipe-logger-test").unwrap();
+
+    let get_xattr = |path: &Path| {
+        let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+        let mut value = [0u8; 16];
+
+        let size = unsafe {
+            libc::getxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_mut_ptr() as *mut std::ffi::c_void,
+                value.len(),
+            )
+        };
+
+        if size < 0 {
+            None
+        } else {
+            Some(value[..size as usize].to_vec())
+        }
+    };
+
+    let new_file = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_compress(true);
+
+        let mut logger = builder.build().unwrap();
+
+        let path = CString::new(test_log_path.as_os_str().as_bytes()).unwrap();
+
+        if unsafe {
+            libc::setxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                b"1".as_ptr() as *const std::ffi::c_void,
+                1,
+                0,
+            )
+        } != 0
+        {
+            // user extended attributes are not supported here
+            fs::remove_dir_all(test_folder).unwrap();
+            return;
+        }
+
+        logger.write_line("This is a log.").unwrap();
+        let new_file = logger.write_line("Isn't it?").unwrap().unwrap();
+
+        assert!(logger.close(Duration::from_millis(WAIT_DURATION_MILLI_SECONDS * 5)).is_complete());
+
+        new_file
+    };
+
+    assert_eq!(Some(b"1".to_vec()), get_xattr(&new_file));
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn build_errors() {
+    let test_folder = create_test_folder();
+
+    assert!(matches!(
+        PipeLoggerBuilder::new(&test_folder).build(),
+        Err(PipeLoggerBuilderError::FileIsDirectory(_))
+    ));
+
+    assert!(matches!(
+        PipeLoggerBuilder::new(Path::join(&test_folder, Path::new("not-exist/logfile.log"))).build(),
+        Err(PipeLoggerBuilderError::IOError(ref err)) if err.kind() == std::io::ErrorKind::NotFound
+    ));
+
+    assert!(matches!(
+        PipeLoggerBuilder::new(Path::join(&test_folder, Path::new(LOG_FILE_NAME)))
+            .set_rotation_naming(RotationNaming::Template(".%Y%m%d%Z".to_string()))
+            .clone()
+            .build(),
+        Err(PipeLoggerBuilderError::InvalidRotationNaming(_))
+    ));
+
+    assert!(matches!(
+        PipeLoggerBuilder::new(Path::join(&test_folder, Path::new(LOG_FILE_NAME)))
+            .set_rotation_naming(RotationNaming::Sequential)
+            .set_max_age(Some(Duration::from_secs(86400)))
+            .clone()
+            .build(),
+        Err(PipeLoggerBuilderError::InvalidRotationNaming(_))
+    ));
+
+    assert!(matches!(
+        PipeLoggerBuilder::new(Path::join(&test_folder, Path::new(LOG_FILE_NAME)))
+            .set_rotation_naming(RotationNaming::Sequential)
+            .set_max_total_size(Some(1024))
+            .clone()
+            .build(),
+        Err(PipeLoggerBuilderError::InvalidRotationNaming(_))
+    ));
+
+    assert!(matches!(
+        PipeLoggerBuilder::new(Path::join(&test_folder, Path::new(LOG_FILE_NAME)))
+            .set_compression_level(CompressionLevel::Fixed(12))
+            .clone()
+            .build(),
+        Err(PipeLoggerBuilderError::InvalidCompressionLevel(12))
+    ));
+
+    assert!(matches!(
+        PipeLoggerBuilder::new(Path::join(&test_folder, Path::new(LOG_FILE_NAME)))
+            .set_compression_level(CompressionLevel::Adaptive(AdaptiveCompressionLevel {
+                fast_level:          0,
+                max_level:           10,
+                min_free_space:      0,
+                min_free_file_sizes: 0,
+            }))
+            .clone()
+            .build(),
+        Err(PipeLoggerBuilderError::InvalidCompressionLevel(10))
+    ));
+
+    #[cfg(feature = "zstd")]
+    {
+        assert!(matches!(
+            PipeLoggerBuilder::new(Path::join(&test_folder, Path::new(LOG_FILE_NAME)))
+                .set_compress_method(CompressionMethod::Zstd)
+                .set_compression_level(CompressionLevel::Fixed(0))
+                .clone()
+                .build(),
+            Err(PipeLoggerBuilderError::InvalidCompressionLevel(0))
+        ));
+
+        PipeLoggerBuilder::new(Path::join(&test_folder, Path::new(LOG_FILE_NAME)))
+            .set_compress_method(CompressionMethod::Zstd)
+            .set_compression_level(CompressionLevel::Fixed(22))
+            .clone()
+            .build()
+            .unwrap();
+
+        fs::remove_file(Path::join(&test_folder, Path::new(LOG_FILE_NAME))).unwrap();
+    }
+
+    // an invalid option leaves nothing behind
+    assert!(matches!(
+        PipeLoggerBuilder::new(Path::join(&test_folder, Path::new(LOG_FILE_NAME)))
+            .set_name_split(NameSplit::Explicit {
+                stem: "other".to_string(), ext: ".log".to_string()
+            })
+            .clone()
+            .build(),
+        Err(PipeLoggerBuilderError::IOError(ref err)) if err.kind() == std::io::ErrorKind::InvalidInput
+    ));
+
+    assert_eq!(0, test_folder.read_dir().unwrap().count());
+
+    #[cfg(unix)]
+    {
+        let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+        let test_link_path = Path::join(&test_folder, Path::new("link.log"));
+
+        fs::write(&test_log_path, "").unwrap();
+        std::os::unix::fs::symlink(LOG_FILE_NAME, &test_link_path).unwrap();
+
+        PipeLoggerBuilder::new(&test_link_path).build().unwrap();
+
+        let mut builder = PipeLoggerBuilder::new(&test_link_path);
+
+        builder.set_follow_symlinks(false);
+
+        assert!(matches!(builder.build(), Err(PipeLoggerBuilderError::IOError(_))));
+    }
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn build_root() {
+    assert!(matches!(
+        PipeLoggerBuilder::new(Path::new("/")).build(),
+        Err(PipeLoggerBuilderError::FileIsDirectory(_))
+    ));
+}
+
+#[cfg(windows)]
+#[test]
+fn build_windows_verbatim_and_unc() {
+    use path_absolutize::Absolutize;
+
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let absolute_log_path = test_log_path.absolutize().unwrap().to_str().unwrap().to_string();
+
+    // \\?\C:\...
+    let verbatim_log_path = format!(r"\\?\{}", absolute_log_path);
+
+    PipeLoggerBuilder::new(&verbatim_log_path).build().unwrap().write_line("verbatim").unwrap();
+
+    // \\localhost\C$\...
+    let unc_log_path =
+        format!(r"\\localhost\{}${}", &absolute_log_path[..1], &absolute_log_path[2..]);
+
+    if let Ok(mut logger) = PipeLoggerBuilder::new(&unc_log_path).build() {
+        // administrative shares may be disabled
+        logger.write_line("unc").unwrap();
+    }
+
+    assert!(fs::read_to_string(&test_log_path).unwrap().starts_with("verbatim\n"));
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[cfg(windows)]
+#[test]
+fn write_rotate_windows_long_path() {
+    use path_absolutize::Absolutize;
+
+    let test_folder = create_test_folder();
+
+    let long_folder = Path::join(&test_folder, "a".repeat(120)).join("b".repeat(120));
+
+    let verbatim_long_folder =
+        PathBuf::from(format!(r"\\?\{}", long_folder.absolutize().unwrap().to_str().unwrap()));
+
+    fs::create_dir_all(&verbatim_long_folder).unwrap();
+
+    let test_log_path = Path::join(&long_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        let new_file = logger.write_line("Isn't it?").unwrap().unwrap();
+
+        assert!(new_file.to_str().unwrap().starts_with(r"\\?\"));
+
+        assert_eq!("This is a log.\nIsn't it?", fs::read_to_string(new_file).unwrap());
+    }
+
+    fs::remove_dir_all(verbatim_long_folder).unwrap();
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn fmt_write() {
+    use std::fmt::Write;
+
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let builder = PipeLoggerBuilder::new(&test_log_path);
+
+        let mut logger = builder.build().unwrap();
+
+        let n = 1;
+
+        writeln!(logger, "This is log #{}.", n).unwrap();
+        write!(logger, "Isn't it?").unwrap();
+    }
+
+    let string = fs::read_to_string(test_log_path).unwrap();
+
+    assert_eq!("This is log #1.\nIsn't it?", string);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn flush_within() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_compress(true);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Isn't it?").unwrap().unwrap();
+
+        logger.write_line("New file!!!!").unwrap();
+
+        let report =
+            logger.flush_within(Duration::from_millis(WAIT_DURATION_MILLI_SECONDS * 5)).unwrap();
+
+        assert!(report.is_complete());
+
+        assert_eq!(2, test_folder.read_dir().unwrap().count());
+    }
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_access() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let builder = PipeLoggerBuilder::new(&test_log_path);
+
+        let mut logger = builder.build().unwrap();
+
+        logger
+            .write_access(&AccessRecord {
+                remote_host: "127.0.0.1".to_string(),
+                ident:       None,
+                user:        Some("frank".to_string()),
+                time:        chrono::DateTime::parse_from_rfc3339("2000-10-10T13:55:36-07:00")
+                    .unwrap(),
+                request:     "GET /apache_pb.gif HTTP/1.0".to_string(),
+                status:      200,
+                bytes:       Some(2326),
+                referer:     Some("http://www.example.com/start.html".to_string()),
+                user_agent:  Some("Mozilla/4.08 [en] (Win98; I ;Nav) \"quoted\"".to_string()),
+            })
+            .unwrap();
+    }
+
+    let string = fs::read_to_string(test_log_path).unwrap();
+
+    assert_eq!(
+        "127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] \"GET /apache_pb.gif HTTP/1.0\" 200 2326 \
+         \"http://www.example.com/start.html\" \"Mozilla/4.08 [en] (Win98; I ;Nav) \
+         \\\"quoted\\\"\"\n",
+        string
+    );
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_w3c() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let new_file = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(110)));
+        builder.set_w3c_fields(Some(vec!["cs-method".to_string(), "cs(User-Agent)".to_string()]));
+
+        let mut logger = builder.build().unwrap();
+
+        assert!(logger.write_w3c(&["GET"]).is_err());
+
+        logger.write_w3c(&["GET", "Mozilla/4.0 (compatible)"]).unwrap();
+        let new_file = logger.write_w3c(&["POST", ""]).unwrap().unwrap();
+
+        logger.write_w3c(&["GET", "curl/8.0"]).unwrap();
+
+        new_file
+    };
+
+    let lines = fs::read_to_string(new_file).unwrap();
+    let lines: Vec<&str> = lines.lines().collect();
+
+    assert_eq!(5, lines.len());
+    assert_eq!("#Version: 1.0", lines[0]);
+    assert_eq!("#Fields: cs-method cs(User-Agent)", lines[1]);
+    assert!(lines[2].starts_with("#Date: "));
+    assert_eq!("GET \"Mozilla/4.0 (compatible)\"", lines[3]);
+    assert_eq!("POST -", lines[4]);
+
+    let lines = fs::read_to_string(test_log_path).unwrap();
+    let lines: Vec<&str> = lines.lines().collect();
+
+    assert_eq!(4, lines.len());
+    assert_eq!("#Version: 1.0", lines[0]);
+    assert_eq!("GET curl/8.0", lines[3]);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_security_event() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let builder = PipeLoggerBuilder::new(&test_log_path);
+
+        let mut logger = builder.build().unwrap();
+
+        let header = SecurityEventHeader {
+            vendor:  "Magic|Len".to_string(),
+            product: "pipe-logger".to_string(),
+            version: "1.0".to_string(),
+        };
+
+        let event = SecurityEvent {
+            signature_id: "100".to_string(),
+            name:         "Login failed".to_string(),
+            severity:     5,
+            extensions:   vec![
+                ("src".to_string(), "10.0.0.1".to_string()),
+                ("msg".to_string(), "a=b\tc".to_string()),
+            ],
+        };
+
+        logger.write_security_event(&header, SecurityEventFormat::Cef, &event).unwrap();
+        logger.write_security_event(&header, SecurityEventFormat::Leef, &event).unwrap();
+    }
+
+    let string = fs::read_to_string(test_log_path).unwrap();
+
+    let lines: Vec<&str> = string.lines().collect();
+
+    assert_eq!(
+        "CEF:0|Magic\\|Len|pipe-logger|1.0|100|Login failed|5|src=10.0.0.1 msg=a\\=b\tc",
+        lines[0]
+    );
+
+    assert_eq!("LEEF:1.0|Magic\\|Len|pipe-logger|1.0|100|sev=5\tsrc=10.0.0.1\tmsg=a=b c", lines[1]);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn search() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_compress(true);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Error: #1").unwrap().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Isn't it?").unwrap().unwrap();
+
+        logger.write_line("Error: #2").unwrap();
+
+        assert!(logger.close(Duration::from_secs(10)).is_complete());
+    }
+
+    let searcher = PipeLogSearcher::new(&test_log_path).unwrap();
+
+    let matches: Vec<Match> =
+        searcher.grep("^Error", None).unwrap().collect::<Result<_, _>>().unwrap();
+
+    assert_eq!(2, matches.len());
+
+    assert_eq!("Error: #1", matches[0].line);
+    assert_eq!(2, matches[0].line_number);
+    assert!(matches[0].path.to_str().unwrap().ends_with(".xz"));
+    assert!(matches[0].timestamp.is_some());
+
+    assert_eq!("Error: #2", matches[1].line);
+    assert_eq!(1, matches[1].line_number);
+    assert_eq!(None, matches[1].timestamp);
+
+    let later = matches[0].timestamp.unwrap() + chrono::Duration::hours(1);
+
+    let matches: Vec<Match> = searcher
+        .grep("^Error", Some(later..later + chrono::Duration::hours(1)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(1, matches.len());
+    assert_eq!("Error: #2", matches[0].line);
+
+    assert!(searcher.grep("(", None).is_err());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn rates() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut logger = PipeLoggerBuilder::new(&test_log_path).build().unwrap();
+
+        assert_eq!(LogRates::default(), logger.rates());
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write("Isn't it?\nYes.").unwrap();
+
+        let rates = logger.rates();
+
+        assert!(rates.last_second.lines <= 2.0);
+        assert_eq!(2.0 / 60.0, rates.last_minute.lines);
+        assert_eq!(29.0 / 60.0, rates.last_minute.bytes);
+        assert_eq!(29.0 / 300.0, rates.last_five_minutes.bytes);
+    }
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_heartbeat() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let rotated_log_path = {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_heartbeat_interval(Some(Duration::from_secs(60)));
+
+        let mut logger = builder.build().unwrap();
+
+        assert_eq!(Some(Duration::from_secs(60)), logger.heartbeat_interval());
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Isn't it?").unwrap().unwrap();
+
+        logger.write_line("Yes.").unwrap();
+        logger.write_heartbeat().unwrap().unwrap()
+    };
+
+    let string = fs::read_to_string(rotated_log_path).unwrap();
+
+    assert_eq!("Yes.\nalive, 3 lines, 1 rotations\n", string);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn volume_alert() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut logger = PipeLoggerBuilder::new(&test_log_path).build().unwrap();
+
+        let alerts = Arc::new(Mutex::new(Vec::new()));
+
+        let callback_alerts = alerts.clone();
+
+        logger.set_volume_alert(
+            VolumeThreshold {
+                lines_per_second: Some(5.0),
+                bytes_per_second: None,
+                sustained:        Duration::from_secs(1),
+            },
+            Box::new(move |alert| callback_alerts.lock().unwrap().push(alert)),
+        );
+
+        for _ in 0..10 {
+            logger.write_line("This is a log.").unwrap();
+        }
+
+        thread::sleep(Duration::from_millis(1100));
+
+        logger.write_line("This is a log.").unwrap();
+
+        let alerts = alerts.lock().unwrap();
+
+        assert_eq!(1, alerts.len());
+        assert!(alerts[0].rate.lines >= 6.0);
+        assert_eq!(Duration::from_secs(1), alerts[0].duration);
+    }
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn write_group() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+
+        logger.begin_group();
+
+        assert_eq!(None, logger.write_line("Isn't it?").unwrap());
+        assert_eq!(None, logger.write_line("Yes.").unwrap());
+
+        assert_eq!("This is a log.\n", fs::read_to_string(&test_log_path).unwrap());
+
+        let rotated_log_file = logger.commit_group().unwrap().unwrap();
+
+        assert_eq!(
+            "This is a log.\nIsn't it?\nYes.\n",
+            fs::read_to_string(rotated_log_file).unwrap()
+        );
+
+        assert_eq!(None, logger.commit_group().unwrap());
+
+        logger.begin_group();
+
+        logger.write_line("Uncommitted.").unwrap();
+    }
+
+    assert_eq!("", fs::read_to_string(&test_log_path).unwrap());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn migrate_naming() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    fs::write(&test_log_path, "").unwrap();
+    fs::write(Path::join(&test_folder, "logfile-2020-01-02-03-04-05-123456.log"), "a").unwrap();
+    fs::write(Path::join(&test_folder, "logfile-2020-01-02-03-04-06-654321.log.xz"), "b").unwrap();
+    fs::write(Path::join(&test_folder, "logfile-unknown.log"), "c").unwrap();
+
+    let migrated_paths = pipe_logger_lib::migrate_naming(
+        &test_log_path,
+        "-%Y-%m-%d-%H-%M-%S-%6f",
+        "-%Y-%m-%d-%H-%M-%S-%3f",
+    )
+    .unwrap();
+
+    assert_eq!(2, migrated_paths.len());
+    assert!(migrated_paths[0].ends_with("logfile-2020-01-02-03-04-05-123.log"));
+    assert!(migrated_paths[1].ends_with("logfile-2020-01-02-03-04-06-654.log.xz"));
+
+    assert_eq!("a", fs::read_to_string(&migrated_paths[0]).unwrap());
+    assert!(Path::join(&test_folder, "logfile-unknown.log").exists());
+    assert_eq!(4, test_folder.read_dir().unwrap().count());
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
+
+#[test]
+fn recognize_historical_naming() {
+    let test_folder = create_test_folder();
+
+    let test_log_path = Path::join(&test_folder, Path::new(LOG_FILE_NAME));
+
+    let oldest = Path::join(&test_folder, "logfile-2020-01-01-00-00-00-500000.log");
+    let older = Path::join(&test_folder, "logfile-2020-01-01-00-00-00-600.log");
+    let newer = Path::join(&test_folder, "logfile-2020-01-01-00-00-01-000000.log.xz");
+    let unknown = Path::join(&test_folder, "logfile-2020-01-01-00-00-00-5000.log");
+
+    for path in [&oldest, &older, &newer, &unknown] {
+        fs::write(path, "old").unwrap();
+    }
+
+    {
+        let mut builder = PipeLoggerBuilder::new(&test_log_path);
+
+        builder.set_rotate(Some(RotateMethod::FileSize(24)));
+        builder.set_count(Some(4));
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write_line("This is a log.").unwrap();
+        logger.write_line("Isn't it?").unwrap().unwrap();
+    }
+
+    assert!(!oldest.exists());
+    assert!(older.exists());
+    assert!(newer.exists());
+    assert!(unknown.exists());
+
+    let searcher = PipeLogSearcher::new(&test_log_path).unwrap();
+
+    let matches: Vec<Match> =
+        searcher.grep("^old$", None).unwrap().filter_map(|m| m.ok()).collect();
+
+    assert_eq!(older.file_name(), matches[0].path.file_name());
+    assert_eq!(600, matches[0].timestamp.unwrap().timestamp_subsec_millis());
+
     fs::remove_dir_all(test_folder).unwrap();
 }
@@ -0,0 +1,58 @@
+#![cfg(feature = "python")]
+
+use std::{ffi::CString, fs, path::Path};
+
+use pyo3::{prelude::*, types::PyDict};
+
+#[test]
+fn write_rotate_close() {
+    let test_folder = Path::join(&Path::join(Path::new("tests"), Path::new("out")), "python");
+
+    fs::create_dir_all(&test_folder).unwrap();
+
+    let log_path = Path::join(&test_folder, "logfile.log");
+
+    pyo3::prepare_freethreaded_python();
+
+    Python::with_gil(|py| {
+        let module = PyModule::new(py, "pipe_logger_lib").unwrap();
+        pipe_logger_lib::python::pipe_logger_lib(&module).unwrap();
+
+        let locals = PyDict::new(py);
+        locals.set_item("pipe_logger_lib", module).unwrap();
+        locals.set_item("log_path", log_path.to_str().unwrap()).unwrap();
+
+        let code = CString::new(
+            r#"
+builder = pipe_logger_lib.PipeLoggerBuilder(log_path)
+builder.set_rotate_file_size(10).set_count(None).set_compress(True)
+
+logger = builder.build()
+
+rotated = logger.write_line("Hello world!")
+assert rotated is not None and str(rotated).endswith(".log.xz")
+
+assert logger.write_line("Hi") is None
+assert logger.rotate() is not None
+assert logger.close(10.0)
+
+try:
+    logger.write_line("Closed")
+    assert False
+except ValueError:
+    pass
+
+try:
+    builder.set_tee("stdin")
+    assert False
+except ValueError:
+    pass
+"#,
+        )
+        .unwrap();
+
+        py.run(&code, None, Some(&locals)).unwrap();
+    });
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
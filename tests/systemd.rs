@@ -0,0 +1,31 @@
+#![cfg(all(feature = "systemd", unix))]
+
+use std::{env, fs, os::unix::net::UnixDatagram, path::Path, time::Duration};
+
+use pipe_logger_lib::*;
+
+#[test]
+fn close_notify() {
+    let test_folder = Path::join(&Path::join(Path::new("tests"), Path::new("out")), "systemd");
+
+    fs::create_dir_all(&test_folder).unwrap();
+
+    let socket_path = env::current_dir().unwrap().join(&test_folder).join("notify.sock");
+
+    let socket = UnixDatagram::bind(&socket_path).unwrap();
+    socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+    env::set_var("NOTIFY_SOCKET", &socket_path);
+
+    let logger = PipeLoggerBuilder::new(Path::join(&test_folder, "logfile.log")).build().unwrap();
+
+    assert!(logger.close(Duration::from_secs(10)).is_complete());
+
+    let mut buffer = [0u8; 256];
+
+    let n = socket.recv(&mut buffer).unwrap();
+
+    assert_eq!(b"STATUS=Syncing the log file", &buffer[..n]);
+
+    fs::remove_dir_all(test_folder).unwrap();
+}
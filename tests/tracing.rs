@@ -0,0 +1,86 @@
+#![cfg(feature = "tracing")]
+
+use std::{
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use pipe_logger_lib::*;
+use tracing::{
+    span::{Attributes, Id, Record},
+    Event, Metadata, Subscriber,
+};
+
+/// Records the names of the created spans.
+struct SpanRecorder {
+    names:   Arc<Mutex<Vec<&'static str>>>,
+    next_id: AtomicU64,
+}
+
+impl Subscriber for SpanRecorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        self.names.lock().unwrap().push(span.metadata().name());
+
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, _event: &Event<'_>) {}
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+#[test]
+fn spans() {
+    let test_folder = Path::join(&Path::join(Path::new("tests"), Path::new("out")), "tracing");
+
+    fs::create_dir_all(&test_folder).unwrap();
+
+    let names = Arc::new(Mutex::new(Vec::new()));
+
+    let recorder = SpanRecorder {
+        names: names.clone(), next_id: AtomicU64::new(1)
+    };
+
+    tracing::subscriber::with_default(recorder, || {
+        let mut builder = PipeLoggerBuilder::new(Path::join(&test_folder, "logfile.log"));
+
+        builder.set_rotate(Some(RotateMethod::FileSize(2)));
+        builder.set_count(Some(2));
+        builder.set_compress(true);
+        builder.set_deterministic(true);
+
+        let mut logger = builder.build().unwrap();
+
+        logger.write("Log\n").unwrap().unwrap();
+        logger.write("Log\n").unwrap().unwrap();
+    });
+
+    let names = names.lock().unwrap();
+
+    for name in [
+        "write_bytes",
+        "rotate_file",
+        "process_rotated_log_file",
+        "process",
+        "compress_log_file",
+        "remove_rotated_log_files",
+    ] {
+        assert!(names.contains(&name), "{}", name);
+    }
+
+    fs::remove_dir_all(test_folder).unwrap();
+}